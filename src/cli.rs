@@ -0,0 +1,271 @@
+use std::path::PathBuf;
+
+use chrono::NaiveDateTime;
+use structopt::StructOpt;
+
+use crate::dates::{parse_from, parse_to};
+use crate::daw_support::{ProjectFormat, ChannelSplit};
+use crate::ingest::DEFAULT_LAYOUT;
+use crate::output::DEFAULT_NAME;
+use crate::mix::MixRule;
+use crate::proxy::ProxyCodec;
+use crate::render::AudioCodec;
+use crate::report::OutputFormat;
+use crate::review::DEFAULT_PLAYER;
+use crate::timeline::DateRange;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "smergy", about = "lines up camera and recorder files and builds projects from them")]
+pub enum Cli {
+    /// list the media found with its timing
+    Scan(ScanArgs),
+    /// show which audio goes with which video
+    Plan(PlanArgs),
+    /// compute where every recording starts relative to its video
+    Sync(SyncArgs),
+    /// go through the groups in a full-screen terminal view to correct and approve them before exporting
+    Review(ReviewArgs),
+    /// write a DAW or editor project for every group
+    Export(ExportArgs),
+    /// mux the synced audio into every video with ffmpeg
+    Render(RenderArgs),
+    /// keep watching the directories and export the sessions new media changes
+    Watch(WatchArgs),
+    /// dump everything known about a single file
+    Inspect(InspectArgs),
+    /// point the proxies in an existing reaper or mlt project back at the original videos
+    Relink(RelinkArgs),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SourceArgs {
+    /// directories to look for media in
+    #[structopt(short, long, parse(from_os_str), required_unless = "manifest")]
+    pub directories: Vec<PathBuf>,
+    /// use the groups and timing saved in a manifest instead of scanning
+    #[structopt(short, long, parse(from_os_str), conflicts_with = "directories")]
+    pub manifest: Option<PathBuf>,
+    /// JSON file of corrections by content hash (start, offset, exclude, group, not_in, device) applied on top of the scan
+    #[structopt(short, long, parse(from_os_str), conflicts_with = "manifest")]
+    pub overrides: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub walk: WalkArgs,
+    #[structopt(flatten)]
+    pub dates: DateArgs,
+}
+
+/// when the recordings looked at were made
+#[derive(StructOpt, Debug)]
+pub struct DateArgs {
+    /// only recordings going on at or after this: a date, a date and time, today, yesterday, a weekday or a time ago like 2d or 6h
+    #[structopt(long, parse(try_from_str = parse_from))]
+    pub from: Option<NaiveDateTime>,
+    /// only recordings started before this, a date alone includes the whole day
+    #[structopt(long, parse(try_from_str = parse_to))]
+    pub to: Option<NaiveDateTime>,
+    /// don't even probe files last modified (well) before --from
+    #[structopt(long, requires = "from")]
+    pub mtime_prefilter: bool,
+}
+
+/// which files of the directories are looked at. a .smergyignore file in any of them lists more patterns to leave out
+#[derive(StructOpt, Debug)]
+pub struct WalkArgs {
+    /// only look at files matching this glob (`*`, `**`, `?`), eg. `**/DCIM/**` or `*.WAV`
+    #[structopt(long)]
+    pub include: Vec<String>,
+    /// leave out files and directories matching this glob, eg. `*.reapeaks` or `Trash/`
+    #[structopt(long)]
+    pub exclude: Vec<String>,
+    /// how many directories deep to look, 1 for just the files in the directories given
+    #[structopt(long)]
+    pub max_depth: Option<usize>,
+    /// follow symbolic links, skipping the ones that link back to where they are
+    #[structopt(long)]
+    pub follow_links: bool,
+    /// look at hidden files and directories (starting with a dot) too
+    #[structopt(long)]
+    pub hidden: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ScanArgs {
+    #[structopt(flatten)]
+    pub source: SourceArgs,
+    /// measure the EBU R128 loudness of every audio recording
+    #[structopt(long)]
+    pub loudness: bool,
+    /// table for reading, json or csv for other tools
+    #[structopt(long, default_value = "table")]
+    pub output: OutputFormat,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct PlanArgs {
+    #[structopt(flatten)]
+    pub source: SourceArgs,
+    /// table for reading, json or csv for other tools. files are in the group of the video with the content hash in `group`
+    #[structopt(long, default_value = "table")]
+    pub output: OutputFormat,
+    /// draw every recording as a bar on a shared time axis, a row per device
+    #[structopt(long)]
+    pub timeline: bool,
+    /// show only the session of this video (as named by ingest, eg. VID_20200105_143210) on the timeline
+    #[structopt(long, requires = "timeline")]
+    pub session: Option<String>,
+    /// show only this span on the timeline, eg. `2020-01-05 14:00..2020-01-05 16:30` or `2020-01-05`
+    #[structopt(long, requires = "timeline", conflicts_with = "session")]
+    pub zoom: Option<DateRange>,
+    /// columns of the timeline
+    #[structopt(long, default_value = "80")]
+    pub width: usize,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SyncArgs {
+    #[structopt(flatten)]
+    pub source: SourceArgs,
+    /// look for claps in the audio as well
+    #[structopt(long)]
+    pub claps: bool,
+    /// save the groups and their timing to a manifest that the other commands can use instead of scanning
+    #[structopt(long, parse(from_os_str))]
+    pub save: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ReviewArgs {
+    #[structopt(flatten)]
+    pub source: SourceArgs,
+    /// manifest to save the reviewed groups and decisions to, the one reviewed by default
+    #[structopt(long, parse(from_os_str))]
+    pub save: Option<PathBuf>,
+    /// command playing a snippet, with {file}, {start} and {length} (seconds) filled in
+    #[structopt(long, default_value = DEFAULT_PLAYER)]
+    pub player: String,
+    /// seconds of every snippet played
+    #[structopt(long, default_value = "5")]
+    pub snippet: f64,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct IngestArgs {
+    /// copy the media into this directory (verified, resumable) before using it, instead of referencing it in place
+    #[structopt(long, parse(from_os_str))]
+    pub ingest_to: Option<PathBuf>,
+    /// directories below --ingest-to, built from {date}, {device} and {session}
+    #[structopt(long, default_value = DEFAULT_LAYOUT)]
+    pub layout: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ProxyArgs {
+    /// make low resolution proxies of the videos and reference them in the projects
+    #[structopt(long)]
+    pub proxies: bool,
+    /// proxy codec: mjpeg or h264 (all-intra)
+    #[structopt(long, default_value = "mjpeg")]
+    pub proxy_codec: ProxyCodec,
+    /// directory for the proxies, a `proxies` directory next to each video by default
+    #[structopt(long, parse(from_os_str))]
+    pub proxy_dir: Option<PathBuf>,
+    /// appended to the file name of a video to name its proxy
+    #[structopt(long, default_value = "_proxy")]
+    pub proxy_suffix: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct OutputArgs {
+    /// directory to write the projects to
+    #[structopt(long, parse(from_os_str), default_value = ".")]
+    pub output_dir: PathBuf,
+    /// project names built from {date}, {time}, {session}, {device} and {first_file}, a `/` makes directories below --output-dir
+    #[structopt(long, default_value = DEFAULT_NAME)]
+    pub name: String,
+    /// replace projects that already exist, instead of adding -2, -3... to the name. the replaced one is kept with a -bak extension
+    #[structopt(long, conflicts_with = "skip-existing")]
+    pub overwrite: bool,
+    /// leave projects that already exist alone and don't export their groups again
+    #[structopt(long)]
+    pub skip_existing: bool,
+    /// refer to the media by absolute paths, instead of relative to the project where possible
+    #[structopt(long)]
+    pub absolute_paths: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExportArgs {
+    #[structopt(flatten)]
+    pub source: SourceArgs,
+    #[structopt(flatten)]
+    pub ingest: IngestArgs,
+    #[structopt(flatten)]
+    pub proxy: ProxyArgs,
+    #[structopt(flatten)]
+    pub output: OutputArgs,
+    /// project format to export: reaper or mlt (Kdenlive/Shotcut)
+    #[structopt(short, long, default_value = "reaper")]
+    pub format: ProjectFormat,
+    /// existing reaper project to add newly overlapping media to, instead of creating new projects
+    #[structopt(long, parse(from_os_str))]
+    pub into: Option<PathBuf>,
+    /// mark claps found in the audio and timecode jumps between parts of a recording in the reaper project
+    #[structopt(long)]
+    pub sync_markers: bool,
+    /// split multichannel recordings into a track per channel: none, mono or pairs
+    #[structopt(long, default_value = "none")]
+    pub split_channels: ChannelSplit,
+    /// measure the EBU R128 loudness of every audio recording
+    #[structopt(long)]
+    pub loudness: bool,
+    /// integrated loudness in LUFS (eg. -16 or -23) reaper items get the gain to reach
+    #[structopt(long, allow_hyphen_values = true)]
+    pub normalize: Option<f64>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct RenderArgs {
+    #[structopt(flatten)]
+    pub source: SourceArgs,
+    #[structopt(flatten)]
+    pub ingest: IngestArgs,
+    /// codec of the rendered audio: aac, pcm or flac
+    #[structopt(long, default_value = "aac")]
+    pub audio_codec: AudioCodec,
+    /// keep the camera's own audio as a second track of the rendered file
+    #[structopt(long)]
+    pub keep_camera_audio: bool,
+    /// mix settings for a device or a file of the rendered mixdown, eg. `zoom-h6:gain=-3,pan=0.5,channels=3-4` or `ZOOM0001_TR2.WAV:mute`
+    #[structopt(long)]
+    pub mix: Vec<MixRule>,
+    /// mix settings of device profiles, a `device:settings` line each. ~/.config/smergy/mix-profiles is read when it exists
+    #[structopt(long, parse(from_os_str))]
+    pub mix_profiles: Option<PathBuf>,
+    /// integrated loudness in LUFS (eg. -16 or -23) to normalize the rendered audio to
+    #[structopt(long, allow_hyphen_values = true)]
+    pub normalize: Option<f64>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct WatchArgs {
+    #[structopt(flatten)]
+    pub export: ExportArgs,
+    /// seconds new files have to stop growing for before they're used
+    #[structopt(long, default_value = "5")]
+    pub settle: u64,
+    /// check the directories every --settle seconds instead of being told of changes, eg. on network shares
+    #[structopt(long)]
+    pub poll: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct InspectArgs {
+    #[structopt(parse(from_os_str))]
+    pub file: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct RelinkArgs {
+    #[structopt(parse(from_os_str))]
+    pub project: PathBuf,
+}
@@ -0,0 +1,260 @@
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{NaiveDateTime, Duration};
+
+use crate::devices::devices;
+use crate::loudness::Loudness;
+use crate::media_file::{MediaFile, MediaType, duration_pretty, name_family};
+
+/// parts recorded back to back may have timestamps this far apart and still count as one recording.
+/// the metadata only keeps whole seconds, a take started by hand right after another one is further apart
+const CONTINUITY_TOLERANCE_SECONDS: i64 = 1;
+
+/// one logical recording - a single file, or the chapters a camera or recorder split a long take into
+#[derive(Debug, Clone)]
+pub struct Clip {
+    pub parts: Vec<MediaFile>,
+    /// start set by hand, eg. in a reviewed manifest, instead of the one read from the metadata
+    start: Option<NaiveDateTime>,
+    /// how many parts per million faster the device's clock ran than the video's
+    pub drift_ppm: f64,
+    /// device profile assigned by hand, instead of the one recognized from the metadata
+    device: Option<&'static str>,
+}
+
+impl Clip {
+    pub fn new(parts: Vec<MediaFile>) -> Self {
+        Self { parts, start: None, drift_ppm: 0., device: None }
+    }
+
+    pub fn single(file: MediaFile) -> Self {
+        Self::new(vec![file])
+    }
+
+    pub fn with_start(mut self, start: NaiveDateTime) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn with_drift(mut self, drift_ppm: f64) -> Self {
+        self.drift_ppm = drift_ppm;
+        self
+    }
+
+    /// uses the profile of the device called `name`, unless there is no such profile
+    pub fn with_device(mut self, name: &str) -> Self {
+        self.device = devices().into_iter().find(|d| d.name == name).map(|d| d.name).or(self.device);
+        self
+    }
+
+    /// the same recording made of other files, eg. its ingested copies
+    pub fn with_parts(&self, parts: Vec<MediaFile>) -> Self {
+        Self { parts, ..self.clone() }
+    }
+
+    pub fn first(&self) -> &MediaFile {
+        &self.parts[0]
+    }
+
+    pub fn media_type(&self) -> Option<MediaType> {
+        self.first().media_type()
+    }
+
+    pub fn device(&self) -> Option<&'static str> {
+        self.device.or_else(|| self.first().device())
+    }
+
+    pub fn filename(&self) -> String {
+        self.first().filename()
+    }
+
+    pub fn channels(&self) -> Option<u16> {
+        self.first().channels()
+    }
+
+    /// the recording starts with the first part, the others follow without gaps
+    pub fn start(&self) -> Option<NaiveDateTime> {
+        self.start.or_else(|| self.first().start())
+    }
+
+    /// where the start comes from: set by hand, or the metadata of a device
+    pub fn timing_source(&self) -> String {
+        match (self.start, self.first().device()) {
+            (Some(_), _) => String::from("manual"),
+            (None, Some("unknown")) | (None, None) => String::from("filesystem"),
+            (None, Some(device)) => format!("{} metadata", device),
+        }
+    }
+
+    pub fn duration(&self) -> Option<Duration> {
+        let seconds = self.parts.iter().map(|p| p.exact_duration()).sum::<Option<f64>>()?;
+        Some(Duration::microseconds((seconds * 1_000_000.).round() as i64))
+    }
+
+    pub fn end(&self) -> Option<NaiveDateTime> {
+        Some(self.start()? + self.duration()?)
+    }
+
+    pub fn offset_from(&self, origin: NaiveDateTime) -> Option<Duration> {
+        Some(self.start()? - origin)
+    }
+
+    /// seconds from the start of the clip to the start of each part, summed from sample counts so the seams line up exactly
+    pub fn part_offsets(&self) -> Vec<f64> {
+        self.parts
+            .iter()
+            .scan(0., |offset, part| {
+                let start = *offset;
+                *offset += part.exact_duration().unwrap_or(0.);
+                Some(start)
+            })
+            .collect()
+    }
+
+    /// the part playing `seconds` into the clip, and how far into that part it is
+    pub fn part_at(&self, seconds: f64) -> Option<(&MediaFile, f64)> {
+        if seconds < 0. {
+            return None
+        }
+        self.parts
+            .iter()
+            .zip(self.part_offsets())
+            .take_while(|(_, offset)| *offset <= seconds)
+            .last()
+            .filter(|(part, offset)| part.exact_duration().is_none_or(|d| seconds < offset + d))
+            .map(|(part, offset)| (part, seconds - offset))
+    }
+
+    fn overlaps_option(&self, other: &Self) -> Option<bool> {
+        Some(self.start()? <= other.end()? && other.start()? <= self.end()?)
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.overlaps_option(other).unwrap_or(false)
+    }
+
+    /// loudness of the whole recording, from the measurements of its parts
+    pub fn loudness(&self) -> Option<Loudness> {
+        let parts: Vec<(Loudness, f64)> = self.parts
+            .iter()
+            .map(|p| Some((p.loudness()?, p.exact_duration()?)))
+            .collect::<Option<_>>()?;
+        Loudness::combine(&parts)
+    }
+
+    /// joins files split by the recording device into clips, every other file becomes a clip of its own.
+    /// parts are found by their names first, then files of the same device that follow each other without a gap are joined
+    pub fn from_files(files: Vec<MediaFile>) -> Vec<Self> {
+        let mut numbered: Vec<((String, u32), MediaFile)> = files
+            .into_iter()
+            .map(|f| (sequence_of(&f.filename()), f))
+            .collect();
+        numbered.sort_by(|(one, _), (other, _)| one.cmp(other));
+
+        let mut clips: Vec<Self> = vec![];
+        let mut previous: Option<(String, u32)> = None;
+        for ((sequence, part), file) in numbered {
+            let follows = match (&previous, clips.last()) {
+                (Some((last_sequence, last_part)), Some(clip)) => {
+                    *last_sequence == sequence
+                        && *last_part + 1 == part
+                        // gopro chapters are named reliably, other devices need the timing to agree
+                        && (sequence.starts_with("gopro") || continues(clip.parts.last().unwrap(), &file))
+                },
+                _ => false,
+            };
+            match (follows, clips.last_mut()) {
+                (true, Some(clip)) => clip.parts.push(file),
+                _ => clips.push(Self::single(file)),
+            }
+            previous = Some((sequence, part));
+        }
+
+        clips.sort_by(|one, other| {
+            (one.device(), one.first().extension(), one.start()).cmp(&(other.device(), other.first().extension(), other.start()))
+        });
+        let mut joined: Vec<Self> = vec![];
+        for clip in clips {
+            // the tracks of a take start together, so the take it continues may not be the last clip
+            let continued = joined.iter_mut().rev().find(|last| {
+                let end = last.parts.last().unwrap();
+                same_family(end, clip.first()) && continues(end, clip.first())
+            });
+            match continued {
+                Some(last) => last.parts.extend(clip.parts),
+                None => joined.push(clip),
+            }
+        }
+        joined.sort_by_key(|c| c.start());
+        joined
+    }
+}
+
+/// a duration as fractional seconds, to the microsecond
+pub(crate) fn seconds(duration: Duration) -> f64 {
+    duration.num_microseconds().unwrap_or(0) as f64 / 1_000_000.
+}
+
+impl Display for Clip {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first())?;
+        if self.parts.len() > 1 {
+            write!(
+                f,
+                " + {} more parts ({} in total)",
+                self.parts.len() - 1,
+                self.duration().and_then(duration_pretty).unwrap_or_else(|| String::from("unknown")),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// (sequence, part) a file belongs to, part 0 being the first file of a recording.
+/// knows GoPro chapters (GOPR1234.MP4, GP011234.MP4, GH011234.MP4, GX021234.MP4)
+/// and numbered continuations (ZOOM0001_LR.WAV, ZOOM0001_LR-0001.WAV), any other file starts a sequence of its own
+pub fn sequence_of(file_name: &str) -> (String, u32) {
+    let upper = file_name.to_uppercase();
+    let (stem, extension) = upper.rsplit_once('.').unwrap_or((&upper, ""));
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if stem.len() == 8 && stem.is_char_boundary(4) {
+        let (prefix, number) = stem.split_at(4);
+        if prefix == "GOPR" && digits(number) {
+            return (format!("gopro {} {}", number, extension), 0)
+        }
+        let (camera, chapter) = prefix.split_at(2);
+        if digits(chapter) && digits(number) {
+            let chapter: u32 = chapter.parse().unwrap_or(0);
+            match camera {
+                // GP01 is the second file of a GOPRxxxx recording
+                "GP" => return (format!("gopro {} {}", number, extension), chapter),
+                // newer cameras start at GH01/GX01
+                "GH" | "GX" => return (format!("gopro {} {} {}", camera, number, extension), chapter.saturating_sub(1)),
+                _ => {},
+            }
+        }
+    }
+    match stem.rsplit_once('-') {
+        Some((base, number)) if number.len() == 4 && digits(number) => {
+            (format!("{} {}", base, extension), number.parse().unwrap_or(0))
+        },
+        _ => (format!("{} {}", stem, extension), 0),
+    }
+}
+
+fn same_family(previous: &MediaFile, next: &MediaFile) -> bool {
+    name_family(&previous.filename()) == name_family(&next.filename())
+}
+
+/// whether `next` picks up where `previous` stopped
+fn continues(previous: &MediaFile, next: &MediaFile) -> bool {
+    let same_source = previous.device() == next.device()
+        && previous.extension() == next.extension()
+        && previous.channels() == next.channels();
+    // whole seconds, so the rounding of the timestamps doesn't look like an overlap
+    let gap = match (previous.end(), next.start()) {
+        (Some(end), Some(start)) => (start - end).num_seconds(),
+        _ => return false,
+    };
+    same_source && (0..=CONTINUITY_TOLERANCE_SECONDS).contains(&gap)
+}
@@ -0,0 +1,586 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::iter::once;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{ScanArgs, PlanArgs, SourceArgs, SyncArgs, ReviewArgs, ExportArgs, OutputArgs, RenderArgs, WatchArgs, InspectArgs, RelinkArgs, IngestArgs};
+use crate::clip::{Clip, seconds};
+use crate::dates::DateFilter;
+use crate::daw_support::{DAWProjectFile, ProjectFormat};
+use crate::daws::mlt::Mlt;
+use crate::daws::reaper::{Reaper, merge_into};
+use crate::daws::rpp::RppNode;
+use crate::dedup::rescanned;
+use crate::ffmpeg_wrapper::Ffmpeg;
+use crate::ingest::{Ingest, Session};
+use crate::manifest::Manifest;
+use crate::media_file::{MediaFile, MediaType, media_files};
+use crate::mix::{load_mix_profiles, default_mix_profiles};
+use crate::output::{backup_path, keep_backup, Collision, ProjectName, ProjectOutput};
+use crate::overrides::Overrides;
+use crate::proxy::{ProxySettings, make_proxies, relink_rpp, relink_mlt};
+use crate::render::Render;
+use crate::report::{Report, OutputFormat};
+use crate::review::Review;
+use crate::terminal::{self, RawMode, ENTER_SCREEN, LEAVE_SCREEN};
+use crate::select::{Select, VideoAudioGroup};
+use crate::sync::{detect_claps, timecode_jumps};
+use crate::timeline::Timeline;
+use crate::walk::WalkFilter;
+use crate::watch::{watch_directories, fingerprints, changed_sessions};
+
+pub fn scan(args: &ScanArgs) -> Result<(), Box<dyn Error>> {
+    let mut media = source_media(&args.source)?;
+    media.sort_by_key(|f| f.start());
+    if args.output != OutputFormat::Table {
+        let clips: Vec<Clip> = media.into_iter().map(Clip::single).collect();
+        print_report(&Report::from_clips(clips.iter().map(|c| (c, None))), args.output);
+        return Ok(())
+    }
+    for file in &media {
+        println!(
+            "{:<19}  {:>8}  {:<8}  {}",
+            file.start_pretty().unwrap_or_else(|| String::from("unknown")),
+            file.duration_pretty().unwrap_or_else(|| String::from("unknown")),
+            file.device().unwrap_or("unknown"),
+            file.full_path(),
+        );
+        for duplicate in file.duplicates() {
+            println!("{:<39}  also at {}", "", duplicate.display());
+        }
+        if args.loudness && file.channels().is_some() {
+            match file.loudness() {
+                Some(loudness) => println!("{:<39}  {}", "", loudness),
+                None => println!("{:<39}  loudness unknown", ""),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn plan(args: &PlanArgs) -> Result<(), Box<dyn Error>> {
+    if args.timeline {
+        return timeline(args)
+    }
+    let groups = load_groups(&args.source)?;
+    if args.output != OutputFormat::Table {
+        let clips = groups.iter().flat_map(|(video, audios)| once(video).chain(audios.iter()).map(move |c| (c, Some(video))));
+        print_report(&Report::from_clips(clips), args.output);
+        return Ok(())
+    }
+    for (video, audios) in groups {
+        println!("\n# {}", video);
+        for audio in &audios {
+            println!("#   {}", audio);
+        }
+    }
+    Ok(())
+}
+
+pub fn sync(args: &SyncArgs) -> Result<(), Box<dyn Error>> {
+    let groups = load_groups(&args.source)?;
+    if let Some(path) = &args.save {
+        Manifest::from_groups(&groups).save(path)?;
+        println!("# saved {} groups to {}", groups.len(), path.display());
+    }
+    for (video, audios) in groups {
+        println!("\n# {}", video);
+        let video_start = match video.start() {
+            Some(start) => start,
+            None => continue,
+        };
+        for clip in once(&video).chain(audios.iter()) {
+            let offset = clip.offset_from(video_start).map(seconds);
+            match offset {
+                Some(offset) => println!("{:+12.3}s  {}", offset, clip.filename()),
+                None => println!("{:>13}  {}", "unknown", clip.filename()),
+            }
+            if !args.claps {
+                continue;
+            }
+            for (part, part_offset) in clip.parts.iter().zip(clip.part_offsets()) {
+                for anchor in detect_claps(part) {
+                    println!("{:+12.3}s    {}", offset.unwrap_or(0.) + part_offset + anchor.position, anchor.label);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// corrections go to the manifest (`--save` or the one reviewed) and to the overrides file when there is one
+pub fn review(args: &ReviewArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(path) = &args.source.overrides {
+        if !path.exists() {
+            Overrides::default().save(path)?;
+        }
+    }
+    let (groups, approvals) = match &args.source.manifest {
+        Some(path) => {
+            let manifest = Manifest::load(path)?;
+            (manifest.groups()?, manifest.groups.iter().map(|g| g.approved).collect())
+        },
+        None => (load_groups(&args.source)?, vec![]),
+    };
+    let mut review = Review::new(groups, &approvals).with_player(&args.player, args.snippet);
+    let stdin = io::stdin();
+    // commands piped in, or a terminal that can't be switched to raw mode, get the line prompt
+    match (terminal::interactive() && !review.is_empty()).then(RawMode::enable).flatten() {
+        Some(raw_mode) => {
+            let mut stdout = io::stdout();
+            write!(stdout, "{}", ENTER_SCREEN)?;
+            let reviewed = review.run_screen(stdin.lock(), &mut stdout, terminal::size());
+            write!(stdout, "{}", LEAVE_SCREEN)?;
+            drop(raw_mode);
+            reviewed?;
+        },
+        None => review.run(stdin.lock(), io::stdout())?,
+    }
+
+    let manifest_path = args.save.as_ref().or(args.source.manifest.as_ref());
+    if let Some(path) = manifest_path {
+        review.manifest().save(path)?;
+        println!("# saved the review to {}", path.display());
+    }
+    if let Some(path) = &args.source.overrides {
+        let mut overrides = Overrides::load(path)?;
+        review.update_overrides(&mut overrides);
+        overrides.save(path)?;
+        println!("# saved the corrections to {}", path.display());
+    }
+    if manifest_path.is_none() && args.source.overrides.is_none() {
+        println!("# nothing saved, --save or --overrides keep the review");
+    }
+    Ok(())
+}
+
+pub fn export(args: &ExportArgs) -> Result<(), Box<dyn Error>> {
+    println!("FFMPEG version found: {}\n", Ffmpeg::version().unwrap_or_default());
+    if let Some(project_path) = &args.into {
+        return merge_into_project(project_path, &args.source);
+    }
+    for (video, audios) in ingested_groups(&args.source, &args.ingest)? {
+        println!("\n# {}", video);
+        export_group(args, &video, &audios)?;
+    }
+    Ok(())
+}
+
+/// writes the project of a single group as `args` say
+fn export_group(args: &ExportArgs, video: &Clip, audios: &[Clip]) -> Result<(), Box<dyn Error>> {
+    let gains = match args.loudness || args.normalize.is_some() {
+        true => loudness_report(audios, args.normalize),
+        false => HashMap::new(),
+    };
+    let proxies = match args.proxy.proxies {
+        true => {
+            let settings = ProxySettings::new(args.proxy.proxy_codec)
+                .in_directory(args.proxy.proxy_dir.clone())
+                .with_suffix(&args.proxy.proxy_suffix);
+            make_proxies(&settings, video.parts.iter().map(|p| p.entry.path()))
+        },
+        false => HashMap::new(),
+    };
+
+    match args.format {
+        ProjectFormat::Reaper => {
+            let anchors: Vec<_> = match args.sync_markers {
+                true => once(video)
+                    .chain(audios.iter())
+                    .flat_map(|c| c.parts.iter())
+                    .flat_map(|f| detect_claps(f).into_iter().map(move |a| (f, a)))
+                    .chain(once(video).chain(audios.iter()).flat_map(timecode_jumps))
+                    .collect(),
+                false => vec![],
+            };
+            let project_file = Reaper::from_group(video, audios, &anchors, args.split_channels)
+                .with_item_gains(&gains)
+                .with_proxies(&proxies);
+            save_project(&args.output, project_file, video, audios, Reaper::with_relative_paths, Reaper::with_absolute_paths)?;
+        },
+        ProjectFormat::Mlt => {
+            if let Some(project_file) = Mlt::from_group(video, audios, args.split_channels) {
+                let project_file = project_file.with_proxies(&proxies);
+                save_project(&args.output, project_file, video, audios, Mlt::with_relative_paths, Mlt::with_absolute_paths)?;
+            }
+        },
+    }
+    Ok(())
+}
+
+/// saves the project of a group where `args` say, with its media relative to it or by absolute paths when asked.
+/// a project it replaces is kept next to it with a -bak extension
+fn save_project<P: DAWProjectFile>(
+    args: &OutputArgs,
+    project_file: P,
+    video: &Clip,
+    audios: &[Clip],
+    relative_to: impl Fn(P, &Path) -> P,
+    absolute: impl Fn(P) -> P,
+) -> Result<(), Box<dyn Error>> {
+    let collision = match (args.overwrite, args.skip_existing) {
+        (true, _) => Collision::Overwrite,
+        (_, true) => Collision::Skip,
+        _ => Collision::Suffix,
+    };
+    let output = ProjectOutput::new(args.output_dir.clone(), &args.name)
+        .with_collision(collision)
+        .with_relative_media(!args.absolute_paths);
+    let path = match output.path_for(&ProjectName::of(video, audios), project_file.extension()) {
+        Some(path) => path,
+        None => {
+            println!("# a project for {} already exists, skipping", video.filename());
+            return Ok(())
+        },
+    };
+    let project_file = match (output.relative_media(), path.parent()) {
+        (true, Some(directory)) => {
+            fs::create_dir_all(directory)?;
+            relative_to(project_file, directory)
+        },
+        _ => absolute(project_file),
+    };
+    if let Some(backup) = keep_backup(&path)? {
+        println!("# kept the previous version as {}", backup.display());
+    }
+    project_file.save(&path)?;
+    println!("# saved {}", path.display());
+    Ok(())
+}
+
+pub fn render(args: &RenderArgs) -> Result<(), Box<dyn Error>> {
+    println!("FFMPEG version found: {}\n", Ffmpeg::version().unwrap_or_default());
+    let profiles = match args.mix_profiles.clone().or_else(default_mix_profiles) {
+        Some(path) => load_mix_profiles(&path)?,
+        None => vec![],
+    };
+    let rules = [profiles, args.mix.clone()].concat();
+    for (video, audios) in ingested_groups(&args.source, &args.ingest)? {
+        println!("\n# {}", video);
+        let render = match Render::from_group(&video, &audios, &rules) {
+            Some(render) => render
+                .with_codec(args.audio_codec)
+                .keeping_camera_audio(args.keep_camera_audio)
+                .normalized_to(args.normalize),
+            None => {
+                println!("# can't line up {} with audible audio, skipping", video.filename());
+                continue;
+            },
+        };
+        match render.run() {
+            Some(()) => println!("# rendered {}", render.output().display()),
+            None => println!("# rendering {} failed", render.output().display()),
+        }
+    }
+    Ok(())
+}
+
+/// exports the sessions changed by media added under the directories, until interrupted.
+/// what's there when it starts is taken as already exported. a session exported again is saved next to
+/// the project it had, so edits made to that one aren't lost
+pub fn watch(args: &WatchArgs) -> Result<(), Box<dyn Error>> {
+    let source = &args.export.source;
+    if source.manifest.is_some() || args.export.into.is_some() {
+        return Err("watch scans the directories and writes new projects, it can't use --manifest or --into".into())
+    }
+    let ingest = ingest_of(&args.export.ingest);
+    let mut media = Select::media_files(&source.directories, &walk_filter(source));
+    let overrides_at_start = overrides(source)?;
+    let mut sessions = fingerprints(&Select::groups(scanned_clips(source, media.clone(), &overrides_at_start), &overrides_at_start));
+    println!("# watching {} sessions in {} directories", sessions.len(), source.directories.len());
+
+    let filter = walk_filter(source);
+    let settle = std::time::Duration::from_secs(args.settle);
+    watch_directories(&source.directories, args.poll, settle, |changed| {
+        let changed: Vec<&PathBuf> = changed
+            .iter()
+            .filter(|path| source.directories.iter().any(|root| filter.admits(root, path)))
+            .collect();
+        if changed.is_empty() {
+            return
+        }
+        for path in &changed {
+            println!("# {} {}", if path.exists() { "found" } else { "lost" }, path.display());
+        }
+        let changed: Vec<&Path> = changed.iter().map(|p| p.as_path()).collect();
+        media = rescanned(std::mem::take(&mut media), &changed, |path| media_files(&path));
+        let overrides = match overrides(source) {
+            Ok(overrides) => overrides,
+            Err(error) => {
+                println!("# {}, ignoring the overrides", error);
+                Overrides::default()
+            },
+        };
+        let groups = Select::groups(scanned_clips(source, media.clone(), &overrides), &overrides);
+        let updated = fingerprints(&groups);
+        let changed_sessions = changed_sessions(&sessions, &updated);
+        sessions = updated;
+        let changed_groups = groups.into_iter().filter(|(video, _)| {
+            let session = Session::of(video);
+            changed_sessions.contains(&format!("{}/{}", session.date, session.name))
+        });
+        for (video, audios) in ingested(changed_groups.collect(), &ingest) {
+            println!("\n# {}", video);
+            if let Err(error) = export_group(&args.export, &video, &audios) {
+                println!("# exporting failed: {}", error);
+            }
+        }
+    })?;
+    Ok(())
+}
+
+pub fn inspect(args: &InspectArgs) -> Result<(), Box<dyn Error>> {
+    let file = media_files(&args.file)
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("{} is not a supported media file", args.file.display()))?;
+    let unknown = || String::from("unknown");
+    println!("file:         {}", file.full_path());
+    println!("device:       {}", file.device().unwrap_or("unknown"));
+    println!("start:        {}", file.start_pretty().unwrap_or_else(unknown));
+    println!("duration:     {}", file.exact_duration().map(|d| format!("{:.6}s", d)).unwrap_or_else(unknown));
+    println!("content hash: {}", file.content_hash().unwrap_or_else(unknown));
+    if let Some((width, height)) = file.resolution() {
+        println!("resolution:   {}x{}", width, height);
+    }
+    if let Some((num, den)) = file.frame_rate() {
+        println!("frame rate:   {}/{}", num, den);
+    }
+    if let Some(rate) = file.sample_rate() {
+        println!("sample rate:  {}", rate);
+    }
+    if let Some(channels) = file.channels() {
+        println!("channels:     {}", channels);
+    }
+    let mut names: Vec<_> = file.channel_names().into_iter().collect();
+    names.sort();
+    for (channel, name) in names {
+        println!("  channel {}:  {}", channel, name);
+    }
+    println!("\n{}", serde_json::to_string_pretty(&file.ffmpeg_data_raw())?);
+    Ok(())
+}
+
+fn timeline(args: &PlanArgs) -> Result<(), Box<dyn Error>> {
+    let (clips, groups) = match &args.source.manifest {
+        Some(_) => {
+            let groups = load_groups(&args.source)?;
+            let mut clips: Vec<Clip> = vec![];
+            for clip in groups.iter().flat_map(|(video, audios)| once(video).chain(audios.iter())) {
+                if !clips.iter().any(|c| c.first().full_path() == clip.first().full_path()) {
+                    clips.push(clip.clone());
+                }
+            }
+            (clips, groups)
+        },
+        None => {
+            let overrides = overrides(&args.source)?;
+            let clips = scanned_clips(&args.source, Select::media_files(&args.source.directories, &walk_filter(&args.source)), &overrides);
+            let groups = Select::groups(clips.clone(), &overrides);
+            (clips, groups)
+        },
+    };
+    let timeline = match Timeline::of(&clips, &groups) {
+        Some(timeline) => timeline.with_width(args.width),
+        None => {
+            println!("# nothing with a known start and end to draw");
+            return Ok(())
+        },
+    };
+    let timeline = match (&args.session, &args.zoom) {
+        (Some(session), _) => {
+            let video = groups
+                .iter()
+                .map(|(video, _)| video)
+                .chain(clips.iter())
+                .find(|v| matches!(v.media_type(), Some(MediaType::Video)) && Session::of(v).name == *session)
+                .ok_or_else(|| format!("no session called {}", session))?;
+            timeline.around(video)
+        },
+        (None, Some(range)) => timeline.zoomed(range),
+        (None, None) => timeline,
+    };
+    let untimed = clips.iter().filter(|c| c.end().is_none()).count();
+    if untimed > 0 {
+        println!("# {} recordings without a known start or duration aren't shown", untimed);
+    }
+    println!("{}", timeline.render());
+    Ok(())
+}
+
+/// swaps the proxies of a project for their originals, the previous version is kept next to it with a -bak extension
+pub fn relink(args: &RelinkArgs) -> Result<(), Box<dyn Error>> {
+    let project_path = &args.project;
+    let source = fs::read_to_string(project_path)?;
+    let directory = project_directory(project_path);
+    let (relinked, count) = match project_path.extension().and_then(|e| e.to_str()) {
+        Some("mlt") => relink_mlt(&source, directory),
+        _ => {
+            let mut project = RppNode::parse(&source)?;
+            let count = relink_rpp(&mut project, directory);
+            (project.to_string(), count)
+        },
+    };
+    if count == 0 {
+        println!("# no proxies to relink in {}", project_path.display());
+        return Ok(())
+    }
+    fs::write(backup_path(project_path), source)?;
+    fs::write(project_path, relinked)?;
+    println!("# relinked {} proxies in {}", count, project_path.display());
+    Ok(())
+}
+
+/// groups from the manifest when there is one, otherwise from scanning the directories
+fn load_groups(source: &SourceArgs) -> Result<Vec<VideoAudioGroup>, Box<dyn Error>> {
+    if let Some(path) = &source.manifest {
+        let dates = date_filter(source);
+        let groups = Manifest::load(path)?.without_rejected().groups()?;
+        return Ok(groups.into_iter().filter(|(video, _)| dates.keeps(video.start(), video.end())).collect())
+    }
+    let media = Select::media_files(&source.directories, &walk_filter(source));
+    report_duplicates(&media);
+    let overrides = overrides(source)?;
+    Ok(Select::groups(scanned_clips(source, media, &overrides), &overrides))
+}
+
+/// clips of the scanned `media` with the overrides applied, made within the dates asked for
+fn scanned_clips(source: &SourceArgs, media: Vec<MediaFile>, overrides: &Overrides) -> Vec<Clip> {
+    date_filter(source).clips(Select::clips(media, overrides))
+}
+
+fn date_filter(source: &SourceArgs) -> DateFilter {
+    DateFilter::new(source.dates.from, source.dates.to)
+}
+
+fn walk_filter(source: &SourceArgs) -> WalkFilter {
+    let walk = &source.walk;
+    WalkFilter::default()
+        .including(&walk.include)
+        .excluding(&walk.exclude)
+        .with_max_depth(walk.max_depth)
+        .following_links(walk.follow_links)
+        .with_hidden(walk.hidden)
+        .modified_since(match source.dates.mtime_prefilter {
+            true => date_filter(source).modified_since(),
+            false => None,
+        })
+}
+
+fn overrides(source: &SourceArgs) -> Result<Overrides, Box<dyn Error>> {
+    match &source.overrides {
+        Some(path) => Ok(Overrides::load(path)?),
+        None => Ok(Overrides::default()),
+    }
+}
+
+/// every file of the source, the ones the manifest lists when there is one
+fn source_media(source: &SourceArgs) -> Result<Vec<MediaFile>, Box<dyn Error>> {
+    if source.manifest.is_none() {
+        let media = Select::media_files(&source.directories, &walk_filter(source));
+        return Ok(date_filter(source).files(overrides(source)?.without_excluded(media)))
+    }
+    let mut media: Vec<MediaFile> = load_groups(source)?
+        .into_iter()
+        .flat_map(|(video, audios)| once(video).chain(audios))
+        .flat_map(|clip| clip.parts)
+        .collect();
+    // recordings overlapping several videos are listed in each of their groups
+    media.sort_by(|one, other| one.full_path().cmp(other.full_path()));
+    media.dedup_by(|one, other| one.full_path() == other.full_path());
+    Ok(media)
+}
+
+/// groups of the source, brought in as `ingest` says. groups failing to ingest are reported and left out
+fn ingested_groups(source: &SourceArgs, ingest: &IngestArgs) -> Result<Vec<VideoAudioGroup>, Box<dyn Error>> {
+    Ok(ingested(load_groups(source)?, &ingest_of(ingest)))
+}
+
+fn ingest_of(args: &IngestArgs) -> Ingest {
+    match &args.ingest_to {
+        Some(destination) => Ingest::copying_to(destination.clone(), &args.layout),
+        None => Ingest::in_place(),
+    }
+}
+
+/// `groups` brought in by `ingest`, groups failing to ingest are reported and left out
+fn ingested(groups: Vec<VideoAudioGroup>, ingest: &Ingest) -> Vec<VideoAudioGroup> {
+    groups
+        .into_iter()
+        .filter_map(|(video, audios)| {
+            let session = Session::of(&video);
+            let ingested = ingest
+                .ingest(&video, &session)
+                .and_then(|video| Ok((video, audios.iter().map(|a| ingest.ingest(a, &session)).collect::<Result<Vec<_>, _>>()?)));
+            match ingested {
+                Ok(group) => Some(group),
+                Err(error) => {
+                    println!("\n# {}, skipping {}", error, video);
+                    None
+                },
+            }
+        })
+        .collect()
+}
+
+/// where copies of files were found, on stderr so `plan --output json` and `csv` stay machine readable
+fn report_duplicates(media: &[MediaFile]) {
+    for file in media.iter().filter(|f| !f.duplicates().is_empty()) {
+        eprintln!("# {} also found at:", file.full_path());
+        for duplicate in file.duplicates() {
+            eprintln!("#   {}", duplicate.display());
+        }
+    }
+}
+
+/// prints the loudness of every recording, returning the gain (dB by file path) bringing each one to `target`
+fn loudness_report(audios: &[Clip], target: Option<f64>) -> HashMap<String, f64> {
+    let mut gains = HashMap::new();
+    for clip in audios {
+        let loudness = match clip.loudness() {
+            Some(loudness) => loudness,
+            None => {
+                println!("#   {}: loudness unknown", clip.filename());
+                continue;
+            },
+        };
+        println!("#   {}: {}", clip.filename(), loudness);
+        if let Some(target) = target {
+            for part in &clip.parts {
+                gains.insert(String::from(part.full_path()), loudness.gain_to(target));
+            }
+        }
+    }
+    gains
+}
+
+/// adds media from the source directories to an existing project, the previous version is kept next to it as .rpp-bak
+fn merge_into_project(project_path: &Path, source: &SourceArgs) -> Result<(), Box<dyn Error>> {
+    let original = fs::read_to_string(project_path)?;
+    let mut project = RppNode::parse(&original)?;
+    let added = merge_into(&mut project, &source_media(source)?, project_directory(project_path));
+    if added.is_empty() {
+        println!("# nothing new to add to {}", project_path.display());
+        return Ok(())
+    }
+    for name in &added {
+        println!("# adding {}", name);
+    }
+    fs::write(backup_path(project_path), original)?;
+    fs::write(project_path, project.to_string())?;
+    Ok(())
+}
+
+/// where relative media paths in the project at `path` start from
+fn project_directory(path: &Path) -> &Path {
+    path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."))
+}
+
+fn print_report(report: &Report, format: OutputFormat) {
+    match format {
+        OutputFormat::Csv => print!("{}", report.to_csv()),
+        _ => println!("{}", report.to_json()),
+    }
+}
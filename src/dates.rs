@@ -0,0 +1,109 @@
+use std::time::{Duration as StdDuration, SystemTime, UNIX_EPOCH};
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+
+use crate::clip::Clip;
+use crate::media_file::MediaFile;
+
+/// how much earlier than `--from` a file may have been modified and still be probed. clocks and time zones are off by hours at times
+const MODIFIED_SLACK_HOURS: i64 = 24;
+
+/// a moment written as a date (`2020-01-05`), a date and time (`2020-01-05 14:30`), `today`, `yesterday`, a weekday
+/// (the last one before today) or a time ago (`90m`, `6h`, `2d`, `1w`).
+/// days stand for their first moment, or the first moment of the next day at the `end` of a range
+pub fn parse_moment(moment: &str, end: bool, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let moment = moment.trim();
+    for format in &["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(moment, format) {
+            return Some(time)
+        }
+    }
+    if let Some(ago) = parse_ago(moment) {
+        return Some(now - ago)
+    }
+    let today = now.date();
+    let day = match moment.to_lowercase().as_str() {
+        "today" => today,
+        "yesterday" => today.pred(),
+        other => match other.parse::<Weekday>() {
+            Ok(weekday) => {
+                let back = (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday() - 1) % 7 + 1;
+                today - Duration::days(back as i64)
+            },
+            Err(_) => NaiveDate::parse_from_str(moment, "%Y-%m-%d").ok()?,
+        },
+    };
+    let start = day.and_hms(0, 0, 0);
+    match end {
+        true => Some(start + Duration::days(1)),
+        false => Some(start),
+    }
+}
+
+/// `90m`, `6h`, `2d` or `1w`
+fn parse_ago(ago: &str) -> Option<Duration> {
+    let split = ago.find(|c: char| !c.is_ascii_digit())?;
+    let amount: i64 = ago[..split].parse().ok()?;
+    match &ago[split..] {
+        "s" => Some(Duration::seconds(amount)),
+        "m" | "min" => Some(Duration::minutes(amount)),
+        "h" => Some(Duration::hours(amount)),
+        "d" => Some(Duration::days(amount)),
+        "w" => Some(Duration::weeks(amount)),
+        _ => None,
+    }
+}
+
+/// for `--from`
+pub fn parse_from(moment: &str) -> Result<NaiveDateTime, String> {
+    parse_moment(moment, false, Local::now().naive_local()).ok_or_else(|| format!("unsupported date: {}", moment))
+}
+
+/// for `--to`, a day given alone is included whole
+pub fn parse_to(moment: &str) -> Result<NaiveDateTime, String> {
+    parse_moment(moment, true, Local::now().naive_local()).ok_or_else(|| format!("unsupported date: {}", moment))
+}
+
+/// keeps the recordings made (at least partly) within a span of time
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DateFilter {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+impl DateFilter {
+    pub fn new(from: Option<NaiveDateTime>, to: Option<NaiveDateTime>) -> Self {
+        Self { from, to }
+    }
+
+    /// whether a recording from `start` to `end` overlaps the span. recordings of unknown timing are only kept without limits
+    pub fn keeps(&self, start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> bool {
+        let after_from = match (self.from, end) {
+            (None, _) => true,
+            (Some(from), Some(end)) => end >= from,
+            (Some(_), None) => false,
+        };
+        let before_to = match (self.to, start) {
+            (None, _) => true,
+            (Some(to), Some(start)) => start < to,
+            (Some(_), None) => false,
+        };
+        after_from && before_to
+    }
+
+    pub fn clips(&self, clips: Vec<Clip>) -> Vec<Clip> {
+        clips.into_iter().filter(|c| self.keeps(c.start(), c.end())).collect()
+    }
+
+    pub fn files(&self, files: Vec<MediaFile>) -> Vec<MediaFile> {
+        files.into_iter().filter(|f| self.keeps(f.start(), f.end())).collect()
+    }
+
+    /// files last modified before this can't hold anything recorded within the span, a copy is modified after its original.
+    /// none without a start
+    pub fn modified_since(&self) -> Option<SystemTime> {
+        let earliest = self.from? - Duration::hours(MODIFIED_SLACK_HOURS);
+        let timestamp = Local.from_local_datetime(&earliest).earliest()?.timestamp();
+        Some(UNIX_EPOCH + StdDuration::from_secs(timestamp.max(0) as u64))
+    }
+}
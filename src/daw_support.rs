@@ -1,11 +1,18 @@
 use std::fs::File;
 use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::collections::HashMap;
 
 pub trait DAWProjectFile {
     fn project_file(&self) -> String;
-    fn filename(&self) -> String;
-    fn save(&self) -> std::io::Result<()> {
-        let mut file = File::create(self.filename())?;
+    fn extension(&self) -> &'static str;
+    /// writes the project to `path`, making the directories it's in
+    fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(directory) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(directory)?;
+        }
+        let mut file = File::create(path)?;
         file.write_all(self.project_file().as_bytes())
     }
 }
@@ -13,3 +20,109 @@ pub trait DAWProjectFile {
 pub trait Track {
     fn as_string(&self) -> String;
 }
+
+/// project formats smergy can export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProjectFormat {
+    Reaper,
+    Mlt,
+}
+
+impl FromStr for ProjectFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "reaper" | "rpp" => Ok(ProjectFormat::Reaper),
+            "mlt" | "kdenlive" | "shotcut" => Ok(ProjectFormat::Mlt),
+            other => Err(format!("unsupported project format: {}", other)),
+        }
+    }
+}
+
+/// how multichannel files get split into tracks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelSplit {
+    None,
+    Mono,
+    Pairs,
+}
+
+impl FromStr for ChannelSplit {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(ChannelSplit::None),
+            "mono" => Ok(ChannelSplit::Mono),
+            "pairs" | "stereo" => Ok(ChannelSplit::Pairs),
+            other => Err(format!("unsupported channel split: {}", other)),
+        }
+    }
+}
+
+impl ChannelSplit {
+    /// the channel selections a file with `channels` channels is split into.
+    /// files with up to two channels are never split, an odd channel left after pairing stays mono
+    pub fn mappings(&self, channels: u16) -> Vec<ChannelMapping> {
+        match self {
+            _ if channels <= 2 => vec![ChannelMapping::All],
+            ChannelSplit::None => vec![ChannelMapping::All],
+            ChannelSplit::Mono => (1..=channels).map(ChannelMapping::Mono).collect(),
+            ChannelSplit::Pairs => (1..=channels)
+                .step_by(2)
+                .map(|c| match c == channels {
+                    true => ChannelMapping::Mono(c),
+                    false => ChannelMapping::Pair(c),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// channels of a file a track plays, numbered from 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelMapping {
+    All,
+    Mono(u16),
+    /// the channel and the one after it
+    Pair(u16),
+}
+
+impl ChannelMapping {
+    /// track name suffix, using the names the recorder stored for its channels when there are any
+    pub fn label(&self, names: &HashMap<u16, String>) -> Option<String> {
+        let name = |channel: u16| names.get(&channel).cloned().unwrap_or_else(|| format!("ch{}", channel));
+        match self {
+            ChannelMapping::All => None,
+            ChannelMapping::Mono(c) => Some(name(*c)),
+            ChannelMapping::Pair(c) => match (names.get(c), names.get(&(c + 1))) {
+                (Some(_), _) | (_, Some(_)) => Some(format!("{} + {}", name(*c), name(c + 1))),
+                _ => Some(format!("ch{}-{}", c, c + 1)),
+            },
+        }
+    }
+
+    /// number of channels the selection carries
+    pub fn width(&self, channels: u16) -> u16 {
+        match self {
+            ChannelMapping::All => channels,
+            ChannelMapping::Mono(_) => 1,
+            ChannelMapping::Pair(_) => 2,
+        }
+    }
+}
+
+impl FromStr for ChannelMapping {
+    type Err = String;
+    /// `all`, a single channel (`3`) or a pair starting at a channel (`3-4`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let channel = |c: &str| c.trim().parse::<u16>().ok().filter(|c| *c > 0);
+        match s.trim().to_lowercase().split_once('-') {
+            _ if s.trim().eq_ignore_ascii_case("all") => Ok(ChannelMapping::All),
+            Some((first, second)) => match (channel(first), channel(second)) {
+                (Some(first), Some(second)) if second == first + 1 => Ok(ChannelMapping::Pair(first)),
+                _ => Err(format!("channel pairs are two neighbouring channels, like 3-4, not {}", s)),
+            },
+            None => channel(s).map(ChannelMapping::Mono).ok_or_else(|| format!("unknown channel selection: {}", s)),
+        }
+    }
+}
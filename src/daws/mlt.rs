@@ -0,0 +1,366 @@
+pub use crate::daw_support::{DAWProjectFile, ChannelSplit, ChannelMapping};
+use crate::clip::{Clip, seconds};
+use crate::media_file::{MediaFile, MediaType};
+use crate::output::{absolute_path, media_path};
+use std::iter::once;
+use std::collections::HashMap;
+use std::path::Path;
+use chrono::NaiveDateTime;
+
+/// MLT XML project, readable by Kdenlive and Shotcut
+pub struct Mlt {
+    profile: MltProfile,
+    clips: Vec<MltClip>,
+}
+
+impl Mlt {
+    pub fn new(profile: MltProfile, clips: Vec<MltClip>) -> Self {
+        Self { profile, clips }
+    }
+
+    /// builds a project whose timeline starts with the earliest clip of the group, the parts of a clip follow each other.
+    /// multichannel audio gets an audio track for every channel selection of `split`
+    pub fn from_group(video: &Clip, audios: &[Clip], split: ChannelSplit) -> Option<Self> {
+        let origin = once(video).chain(audios.iter()).filter_map(|c| c.start()).min()?;
+        let lanes = audios.iter().flat_map(|c| {
+            let names = c.first().channel_names();
+            split
+                .mappings(c.channels().unwrap_or(2))
+                .into_iter()
+                .map(move |mapping| (c, mapping.label(&names), mapping))
+        });
+        let audio_clips = lanes.enumerate().flat_map(|(lane, (c, label, mapping))| {
+            MltClip::for_clip(c, origin)
+                .into_iter()
+                .map(move |clip| match &label {
+                    Some(label) => MltClip { name: format!("{} {}", clip.name, label), ..clip },
+                    None => clip,
+                }.with_channel_mapping(mapping).in_lane(lane))
+        });
+        let clips = MltClip::for_clip(video, origin)
+            .into_iter()
+            .chain(audio_clips)
+            .collect();
+        Some(Self::new(MltProfile::from(video.first()), clips))
+    }
+
+    /// refers to the media relative to `directory`, the one the project is saved in, where it can
+    pub fn with_relative_paths(mut self, directory: &Path) -> Self {
+        for clip in self.clips.iter_mut() {
+            clip.path = media_path(&clip.path, directory);
+        }
+        self
+    }
+
+    /// refers to the media by absolute paths, wherever the project is saved
+    pub fn with_absolute_paths(mut self) -> Self {
+        for clip in self.clips.iter_mut() {
+            clip.path = absolute_path(&clip.path);
+        }
+        self
+    }
+
+    /// plays proxies (by original path) instead of the originals
+    pub fn with_proxies(mut self, proxies: &HashMap<String, String>) -> Self {
+        for clip in self.clips.iter_mut() {
+            if let Some(proxy) = proxies.get(&clip.path) {
+                clip.path = proxy.clone();
+            }
+        }
+        self
+    }
+
+    fn producers(&self) -> String {
+        self.clips
+            .iter()
+            .enumerate()
+            .map(|(index, clip)| {
+                let out = self.profile.frames(clip.length).max(1) - 1;
+                let service = match clip.speed == 1. {
+                    true => format!(
+                        "\n    <property name=\"resource\">{}</property>\n    <property name=\"mlt_service\">avformat</property>",
+                        xml_escape(&clip.path),
+                    ),
+                    // the timewarp producer plays the file faster or slower, keeping its pitch
+                    false => format!(
+                        r#"
+    <property name="resource">{speed}:{resource}</property>
+    <property name="mlt_service">timewarp</property>
+    <property name="warp_speed">{speed}</property>
+    <property name="warp_resource">{resource}</property>
+    <property name="warp_pitch">1</property>"#,
+                        speed = clip.speed,
+                        resource = xml_escape(&clip.path),
+                    ),
+                };
+                format!(
+                    r#"
+  <producer id="producer{index}" in="0" out="{out}">
+    <property name="length">{length}</property>{service}
+  </producer>"#,
+                    index = index,
+                    out = out,
+                    length = out + 1,
+                    service = service,
+                )
+            })
+            .collect()
+    }
+
+    /// entries of a single playlist, separated by blanks so that every clip starts at its offset
+    fn playlist(&self, id: usize, clips: &[(usize, &MltClip)]) -> String {
+        let mut cursor = 0;
+        let mut entries = String::new();
+        for (index, clip) in clips {
+            let position = self.profile.frames(clip.offset);
+            if position > cursor {
+                entries.push_str(&format!("\n    <blank length=\"{}\"/>", position - cursor));
+            }
+            let length = self.profile.frames(clip.length).max(1);
+            let filters = clip.channel_filters();
+            match filters.is_empty() {
+                true => entries.push_str(&format!(
+                    "\n    <entry producer=\"producer{}\" in=\"0\" out=\"{}\"/>",
+                    index,
+                    length - 1,
+                )),
+                false => entries.push_str(&format!(
+                    "\n    <entry producer=\"producer{}\" in=\"0\" out=\"{}\">{}\n    </entry>",
+                    index,
+                    length - 1,
+                    filters,
+                )),
+            }
+            cursor = cursor.max(position) + length;
+        }
+        format!("\n  <playlist id=\"playlist{}\">{}\n  </playlist>", id, entries)
+    }
+
+    fn playlists(&self) -> Vec<String> {
+        let mut video: Vec<_> = self.clips
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_video())
+            .collect();
+        video.sort_by(|(_, one), (_, other)| one.offset.partial_cmp(&other.offset).unwrap());
+        // clips sharing a lane (parts of one recording) go into the same playlist, the others get one each
+        let mut audio: Vec<Vec<(usize, &MltClip)>> = vec![];
+        let mut lanes: Vec<usize> = vec![];
+        for (index, clip) in self.clips.iter().enumerate().filter(|(_, c)| !c.is_video()) {
+            match clip.lane.and_then(|lane| lanes.iter().position(|l| *l == lane)) {
+                Some(existing) => audio[existing].push((index, clip)),
+                None => {
+                    lanes.push(clip.lane.unwrap_or(usize::MAX));
+                    audio.push(vec![(index, clip)]);
+                },
+            }
+        }
+
+        once(self.playlist(0, &video))
+            .chain(audio.iter().enumerate().map(|(i, clips)| self.playlist(i + 1, clips)))
+            .collect()
+    }
+
+    fn tractor(&self, playlist_count: usize) -> String {
+        let out = self.clips
+            .iter()
+            .map(|c| self.profile.frames(c.offset) + self.profile.frames(c.length).max(1))
+            .max()
+            .unwrap_or(1) - 1;
+        let tracks: String = (0..playlist_count)
+            .map(|i| match i {
+                0 => String::from("\n    <track producer=\"playlist0\"/>"),
+                i => format!("\n    <track producer=\"playlist{}\" hide=\"video\"/>", i),
+            })
+            .collect();
+        let transitions: String = (1..playlist_count)
+            .map(|i| format!(
+                r#"
+    <transition id="transition{index}">
+      <property name="a_track">0</property>
+      <property name="b_track">{b_track}</property>
+      <property name="mlt_service">mix</property>
+      <property name="always_active">1</property>
+      <property name="sum">1</property>
+    </transition>"#,
+                index = i - 1,
+                b_track = i,
+            ))
+            .collect();
+        format!(
+            "\n  <tractor id=\"tractor0\" in=\"0\" out=\"{}\">{}{}\n  </tractor>",
+            out, tracks, transitions,
+        )
+    }
+}
+
+impl DAWProjectFile for Mlt {
+    fn project_file(&self) -> String {
+        let playlists = self.playlists();
+        format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<mlt LC_NUMERIC="C" version="6.18.0" producer="tractor0">
+  {profile}{producers}{playlists}{tractor}
+</mlt>
+"#,
+            profile = self.profile.as_string(),
+            producers = self.producers(),
+            tractor = self.tractor(playlists.len()),
+            playlists = playlists.join(""),
+        )
+    }
+
+    fn extension(&self) -> &'static str {
+        "mlt"
+    }
+}
+
+pub struct MltProfile {
+    width: u32,
+    height: u32,
+    frame_rate_num: u32,
+    frame_rate_den: u32,
+}
+
+impl MltProfile {
+    pub fn new(width: u32, height: u32, frame_rate_num: u32, frame_rate_den: u32) -> Self {
+        Self { width, height, frame_rate_num, frame_rate_den }
+    }
+
+    /// number of whole frames in `seconds`
+    fn frames(&self, seconds: f64) -> i64 {
+        (seconds * self.frame_rate_num as f64 / self.frame_rate_den as f64).round() as i64
+    }
+
+    fn as_string(&self) -> String {
+        let divisor = gcd(self.width, self.height).max(1);
+        let fps = self.frame_rate_num as f64 / self.frame_rate_den as f64;
+        format!(
+            r#"<profile description="{width}x{height} {fps}fps" width="{width}" height="{height}" progressive="1" sample_aspect_num="1" sample_aspect_den="1" display_aspect_num="{aspect_num}" display_aspect_den="{aspect_den}" frame_rate_num="{frame_rate_num}" frame_rate_den="{frame_rate_den}" colorspace="709"/>"#,
+            width = self.width,
+            height = self.height,
+            fps = (fps * 100.).round() / 100.,
+            aspect_num = self.width / divisor,
+            aspect_den = self.height / divisor,
+            frame_rate_num = self.frame_rate_num,
+            frame_rate_den = self.frame_rate_den,
+        )
+    }
+}
+
+impl From<&MediaFile> for MltProfile {
+    /// falls back to 1080p25 when the video stream isn't probed properly
+    fn from(video: &MediaFile) -> Self {
+        let (width, height) = video.resolution().unwrap_or((1920, 1080));
+        let (num, den) = video.frame_rate().unwrap_or((25, 1));
+        Self::new(width, height, num, den)
+    }
+}
+
+pub struct MltClip {
+    name: String,
+    path: String,
+    offset: f64,
+    length: f64,
+    media_type: MediaType,
+    channels: ChannelMapping,
+    lane: Option<usize>,
+    /// playback speed, other than 1 for recorders whose clock drifted
+    speed: f64,
+}
+
+impl MltClip {
+    pub fn new(name: String, path: String, offset: f64, length: f64, media_type: MediaType) -> Self {
+        Self { name, path, offset, length, media_type, channels: ChannelMapping::All, lane: None, speed: 1. }
+    }
+
+    /// plays the file at `speed`, shortening or lengthening it on the timeline
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.length = self.length * self.speed / speed;
+        self.speed = speed;
+        self
+    }
+
+    /// audio clips in the same lane share a track
+    pub fn in_lane(mut self, lane: usize) -> Self {
+        self.lane = Some(lane);
+        self
+    }
+
+    pub fn with_channel_mapping(mut self, channels: ChannelMapping) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// channelcopy filters moving the selected channels onto the first two (zero based in mlt)
+    fn channel_filters(&self) -> String {
+        let copies = match self.channels {
+            ChannelMapping::All => vec![],
+            ChannelMapping::Mono(channel) => vec![(channel - 1, 0), (channel - 1, 1)],
+            ChannelMapping::Pair(channel) => vec![(channel - 1, 0), (channel, 1)],
+        };
+        copies
+            .into_iter()
+            .filter(|(from, to)| from != to)
+            .map(|(from, to)| format!(
+                r#"
+      <filter mlt_service="channelcopy">
+        <property name="from">{}</property>
+        <property name="to">{}</property>
+      </filter>"#,
+                from,
+                to,
+            ))
+            .collect()
+    }
+
+    pub fn from_media_file(media_file: &MediaFile, origin: NaiveDateTime) -> Option<Self> {
+        Some(Self::new(
+            media_file.filename(),
+            String::from(media_file.full_path()),
+            seconds(media_file.offset_from(origin)?),
+            seconds(media_file.duration()?),
+            media_file.media_type()?,
+        ))
+    }
+
+    /// one clip per part, placed back to back from where the recording starts.
+    /// a clip whose clock drifted plays slightly faster or slower to stay in sync, like in the reaper export
+    pub fn for_clip(clip: &Clip, origin: NaiveDateTime) -> Vec<Self> {
+        let start = clip.offset_from(origin).map(seconds).unwrap_or(0.);
+        let rate = 1. + clip.drift_ppm / 1_000_000.;
+        clip.parts
+            .iter()
+            .zip(clip.part_offsets())
+            .filter_map(|(part, offset)| Some(Self::new(
+                part.filename(),
+                String::from(part.full_path()),
+                start + offset / rate,
+                part.exact_duration()?,
+                part.media_type()?,
+            ).with_speed(rate)))
+            .collect()
+    }
+
+    fn is_video(&self) -> bool {
+        match self.media_type {
+            MediaType::Video => true,
+            MediaType::Audio => false,
+        }
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    match b {
+        0 => a,
+        b => gcd(b, a % b),
+    }
+}
+
+pub(crate) fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -1 +1,3 @@
-pub mod reaper;
\ No newline at end of file
+pub mod reaper;
+pub mod rpp;
+pub mod mlt;
\ No newline at end of file
@@ -1,68 +1,390 @@
-pub use crate::daw_support::{DAWProjectFile, Track};
-use crate::media_file::{MediaFile, MediaType};
+pub use crate::daw_support::{DAWProjectFile, Track, ChannelSplit, ChannelMapping};
+use crate::daws::rpp::RppNode;
+use crate::hashing::{guid, quick_hash};
+use crate::clip::{Clip, seconds};
+use crate::media_file::{MediaFile, MediaType, name_family};
+use crate::output::{absolute_path, media_path};
+use crate::sync::SyncAnchor;
+use std::iter::once;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{NaiveDateTime, Duration};
 
 pub struct Reaper {
     tracks: Vec<ReaperTrack>,
     project_id: String,
+    settings: ReaperSettings,
+    markers: Vec<ReaperMarker>,
 }
 
 impl Reaper {
     pub fn new(tracks: Vec<ReaperTrack>, project_id: String) -> Self {
-        Self { tracks, project_id }
+        Self { tracks, project_id, settings: ReaperSettings::default(), markers: vec![] }
+    }
+
+    /// project for a video and the audio recorded alongside it, laid out on a timeline starting with the earliest clip.
+    /// `anchors` are sync points found in the files, they become markers. multichannel files are split into tracks according to `split`
+    pub fn from_group(
+        video: &Clip,
+        audios: &[Clip],
+        anchors: &[(&MediaFile, SyncAnchor)],
+        split: ChannelSplit,
+    ) -> Self {
+        let clips: Vec<&Clip> = once(video).chain(audios.iter()).collect();
+        let session = video.first().content_hash().unwrap_or_else(|| video.filename());
+        let origin = clips.iter().filter_map(|c| c.start()).min();
+        let position = |clip: &Clip| match origin {
+            Some(origin) => clip.offset_from(origin).map(seconds).unwrap_or(0.),
+            None => 0.,
+        };
+        // where every file starts on the timeline, parts of a clip follow each other
+        let part_positions: HashMap<&str, f64> = clips
+            .iter()
+            .flat_map(|c| c.parts.iter().zip(c.part_offsets()).map(move |(p, offset)| (p.full_path(), position(c) + offset)))
+            .collect();
+        let groups = recording_groups(&clips);
+        let group_of = |clip: &Clip| clips
+            .iter()
+            .position(|c| c.first().full_path() == clip.first().full_path())
+            .and_then(|i| groups[i]);
+
+        let mut tracks = vec![];
+        for (folder, members) in device_folders(&clips) {
+            let color = members.first().map(|c| device_color(c.device().unwrap_or("unknown")));
+            tracks.push(
+                ReaperTrack::empty(guid(&[&session, "folder", &folder]), folder)
+                    .with_folder(FolderState::FolderStart)
+                    .with_color(color)
+            );
+            let children: Vec<(&Clip, ChannelMapping)> = members
+                .into_iter()
+                .flat_map(|c| split.mappings(c.channels().unwrap_or(2)).into_iter().map(move |m| (c, m)))
+                .collect();
+            let last = children.len() - 1;
+            for (index, (clip, mapping)) in children.into_iter().enumerate() {
+                tracks.push(
+                    ReaperTrack::for_clip(clip, &session, mapping, position(clip))
+                        .in_group(group_of(clip))
+                        .with_color(color)
+                        .with_folder(if index == last { FolderState::FolderEnd } else { FolderState::Track })
+                );
+            }
+        }
+        let mut markers: Vec<ReaperMarker> = clips
+            .iter()
+            .flat_map(|c| ReaperMarker::for_clip(c, position(c)))
+            .collect();
+        markers.extend(anchors.iter().map(|(file, anchor)| {
+            let start = part_positions.get(file.full_path()).copied().unwrap_or(0.);
+            ReaperMarker::new(start + anchor.position, &anchor.label)
+        }));
+        let timestamp = origin.map(|o| o.timestamp()).unwrap_or(0);
+        Self::new(tracks, timestamp.to_string())
+            .with_settings(ReaperSettings::from_media(clips.iter().flat_map(|c| c.parts.iter())))
+            .with_markers(markers)
+    }
+
+    /// sets the gain of every item playing one of the files in `gains` (dB by path), eg. to match a loudness target
+    pub fn with_item_gains(mut self, gains: &HashMap<String, f64>) -> Self {
+        for track in self.tracks.iter_mut() {
+            track.items = track.items
+                .drain(..)
+                .map(|item| match gains.get(&item.path) {
+                    Some(gain) => item.with_gain(*gain),
+                    None => item,
+                })
+                .collect();
+        }
+        self
+    }
+
+    /// refers to the media relative to `directory`, the one the project is saved in, where it can
+    pub fn with_relative_paths(mut self, directory: &Path) -> Self {
+        self.tracks = self.tracks.into_iter().map(|t| t.with_relative_paths(directory)).collect();
+        self
+    }
+
+    /// refers to the media by absolute paths, wherever the project is saved
+    pub fn with_absolute_paths(mut self) -> Self {
+        for item in self.tracks.iter_mut().flat_map(|t| t.items.iter_mut()) {
+            item.path = absolute_path(&item.path);
+        }
+        self
+    }
+
+    /// plays proxies (by original path) instead of the originals, the items keep their names
+    pub fn with_proxies(mut self, proxies: &HashMap<String, String>) -> Self {
+        for item in self.tracks.iter_mut().flat_map(|t| t.items.iter_mut()) {
+            if let Some(proxy) = proxies.get(&item.path) {
+                item.path = proxy.clone();
+            }
+        }
+        self
+    }
+
+    pub fn with_markers(mut self, mut markers: Vec<ReaperMarker>) -> Self {
+        markers.sort_by(|one, other| one.position.partial_cmp(&other.position).unwrap());
+        self.markers = markers;
+        self
+    }
+
+    /// reaper numbers markers and regions separately, in timeline order
+    fn marker_nodes(&self) -> Vec<RppNode> {
+        let (mut marker_index, mut region_index) = (0, 0);
+        self.markers
+            .iter()
+            .flat_map(|marker| {
+                let index = match marker.region_end {
+                    Some(_) => { region_index += 1; region_index },
+                    None => { marker_index += 1; marker_index },
+                };
+                marker.as_nodes(index)
+            })
+            .collect()
+    }
+
+    pub fn with_settings(mut self, settings: ReaperSettings) -> Self {
+        self.settings = settings;
+        self
     }
 
     fn project_id(&self) -> String {
         self.project_id.clone()
     }
 
-    fn track_strings(&self) -> Vec<String> {
-        self.tracks.iter().map(|t| t.as_string()).collect()
+    pub fn project(&self) -> RppNode {
+        RppNode::chunk("REAPER_PROJECT")
+            .param("0.1")
+            .quoted("5.983/linux64")
+            .param(self.project_id())
+            .with_children(project_settings(&self.settings))
+            .with_children(self.marker_nodes())
+            .with_children(self.tracks.iter().map(|t| t.as_node()))
+    }
+}
+
+/// id of a track/item/take `role` playing `mapping` of a file.
+/// tracks playing different channels of one file get different ids, whole files keep the ids they always had
+fn channel_guid(session: &str, content: &str, role: &str, mapping: ChannelMapping) -> String {
+    match mapping {
+        ChannelMapping::All => guid(&[session, content, role]),
+        ChannelMapping::Mono(channel) => guid(&[session, content, role, &format!("mono{}", channel)]),
+        ChannelMapping::Pair(channel) => guid(&[session, content, role, &format!("pair{}", channel)]),
+    }
+}
+
+/// videos first, then a folder for each recorder, each sorted by start
+fn device_folders<'a>(files: &[&'a Clip]) -> Vec<(String, Vec<&'a Clip>)> {
+    let mut videos = vec![];
+    let mut devices: BTreeMap<&str, Vec<&Clip>> = BTreeMap::new();
+    for file in files {
+        match file.media_type() {
+            Some(MediaType::Video) => videos.push(*file),
+            _ => devices.entry(file.device().unwrap_or("unknown")).or_default().push(*file),
+        }
+    }
+    once((String::from("Video"), videos))
+        .chain(devices.into_iter().map(|(device, files)| (String::from(device), files)))
+        .filter(|(_, files)| !files.is_empty())
+        .map(|(name, mut files)| {
+            files.sort_by_key(|f| f.start());
+            (name, files)
+        })
+        .collect()
+}
+
+/// clips overlapping each other were recorded together, such clusters of two or more clips get a shared group id
+fn recording_groups(files: &[&Clip]) -> Vec<Option<u32>> {
+    let mut order: Vec<usize> = (0..files.len()).collect();
+    order.sort_by_key(|&i| files[i].start());
+    let mut clusters = vec![0; files.len()];
+    let mut cluster = 0;
+    let mut cluster_end = None;
+    for index in order {
+        let (start, end) = (files[index].start(), files[index].end());
+        if cluster_end.is_some() && start > cluster_end {
+            cluster += 1;
+            cluster_end = None;
+        }
+        cluster_end = cluster_end.max(end);
+        clusters[index] = cluster;
+    }
+    clusters
+        .iter()
+        .map(|c| match clusters.iter().filter(|other| *other == c).count() {
+            1 => None,
+            _ => Some(*c as u32 + 1),
+        })
+        .collect()
+}
+
+/// stable color for a device, in reaper's `0x01bbggrr` format
+pub(crate) fn device_color(device: &str) -> u32 {
+    let hex = guid(&["color", device]);
+    let rgb = u32::from_str_radix(&hex[..6], 16).unwrap_or(0);
+    let (r, g, b) = (rgb >> 16 & 0xff, rgb >> 8 & 0xff, rgb & 0xff);
+    0x0100_0000 | r | g << 8 | b << 16
+}
+
+/// marker, or a region when it has an end
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReaperMarker {
+    position: f64,
+    name: String,
+    region_end: Option<f64>,
+}
+
+impl ReaperMarker {
+    pub fn new(position: f64, name: &str) -> Self {
+        Self { position, name: String::from(name), region_end: None }
+    }
+
+    pub fn region(start: f64, end: f64, name: &str) -> Self {
+        Self { region_end: Some(end), ..Self::new(start, name) }
+    }
+
+    /// start and end markers for every clip, video clips get a region named after the file and its device as well
+    pub fn for_clip(file: &Clip, position: f64) -> Vec<Self> {
+        let end = position + file.duration().map(seconds).unwrap_or(0.);
+        let name = file.filename();
+        let mut markers = vec![
+            Self::new(position, &format!("start {}", name)),
+            Self::new(end, &format!("end {}", name)),
+        ];
+        if let Some(MediaType::Video) = file.media_type() {
+            let device = file.device().unwrap_or("unknown");
+            markers.push(Self::region(position, end, &format!("{} ({})", name, device)));
+        }
+        markers
+    }
+
+    fn as_nodes(&self, index: usize) -> Vec<RppNode> {
+        match self.region_end {
+            None => vec![RppNode::line("MARKER").param(index).param(self.position).quoted(&self.name).param(0)],
+            Some(end) => vec![
+                RppNode::line("MARKER").param(index).param(self.position).quoted(&self.name).param(1),
+                RppNode::line("MARKER").param(index).param(end).quoted("").param(1),
+            ],
+        }
+    }
+}
+
+/// project wide values taken from the media, `None` keeps reaper's defaults
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReaperSettings {
+    pub sample_rate: Option<u32>,
+    pub frame_rate: Option<(u32, u32)>,
+    pub length: Option<f64>,
+}
+
+impl ReaperSettings {
+    /// sample rate of the best audio stream, frame rate of the first video and the span of all files
+    pub fn from_media<'a, I: IntoIterator<Item = &'a MediaFile>>(files: I) -> Self {
+        let files: Vec<&MediaFile> = files.into_iter().collect();
+        let start = files.iter().filter_map(|f| f.start()).min();
+        let end = files.iter().filter_map(|f| f.end()).max();
+        Self {
+            sample_rate: files.iter().filter_map(|f| f.sample_rate()).max(),
+            frame_rate: files.iter().find_map(|f| f.frame_rate()),
+            length: match (start, end) {
+                (Some(start), Some(end)) => Some(seconds(end - start).ceil()),
+                _ => None,
+            },
+        }
+    }
+
+    /// frame rate the way reaper writes it: 30, 29.97, 23.976
+    fn fps(&self) -> String {
+        match self.frame_rate {
+            Some((num, 1)) => num.to_string(),
+            Some((num, den)) => {
+                let fps = format!("{:.3}", num as f64 / den as f64);
+                String::from(fps.trim_end_matches('0').trim_end_matches('.'))
+            },
+            None => String::from("30"),
+        }
     }
 }
 
 impl DAWProjectFile for Reaper {
     fn project_file(&self) -> String {
-        reaper_project_file(self.track_strings(), self.project_id())
+        self.project().to_string()
     }
 
-    fn filename(&self) -> String {
-        let base_name = match self.tracks.first() {
-            Some(t) => t.track_name.clone(),
-            None => String::from("new-project"),
-        };
+    fn extension(&self) -> &'static str {
+        "rpp"
+    }
+}
+
+/// reaper's folder structure is flat, every track says whether it opens or closes a folder
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FolderState {
+    Track,
+    FolderStart,
+    FolderEnd,
+}
 
-        let base_name = base_name.replace(".", "");
-        format!("{}.rpp", base_name)
+impl FolderState {
+    fn isbus(&self) -> [i32; 2] {
+        match self {
+            FolderState::Track => [0, 0],
+            FolderState::FolderStart => [1, 1],
+            FolderState::FolderEnd => [2, -1],
+        }
     }
 }
 
 pub struct ReaperTrack {
     track_id: String,
     track_name: String,
-    track_file_name: String,
-    track_path: String,
-    track_length: String,
-    another_id: String,
-    item_id: String,
-    media_type: MediaType,
+    items: Vec<ReaperItem>,
+    channels: u16,
+    folder: FolderState,
+    color: Option<u32>,
 }
 
-impl From<MediaFile> for ReaperTrack {
-    fn from(media_file: MediaFile) -> Self {
-        Self::new(
-            media_file.filename(),
-            media_file.filename(),
-            media_file.filename(),
-            String::from(media_file.full_path()),
-            format!("{}", media_file.duration().unwrap().num_seconds() + 1),
-            String::from(media_file.filename()),
-            String::from(media_file.filename()),
-            media_file.media_type().expect("Reaper requires media type specifying"),
-        )
+impl ReaperTrack {
+    /// ids are derived from the file's content and the `session`, so exporting the same session again yields the same project
+    pub fn from_media_file(media_file: &MediaFile, session: &str) -> Self {
+        Self::for_channels(media_file, session, ChannelMapping::All)
+    }
+
+    /// track with an item for every part of the clip, starting at `position`.
+    /// a clip whose clock drifted plays slightly faster or slower to stay in sync
+    pub fn for_clip(clip: &Clip, session: &str, mapping: ChannelMapping, position: f64) -> Self {
+        let rate = 1. + clip.drift_ppm / 1_000_000.;
+        let track = Self::for_channels(clip.first(), session, mapping);
+        let track = clip.parts
+            .iter()
+            .zip(clip.part_offsets())
+            .skip(1)
+            .fold(track, |track, (part, offset)| {
+                track.with_item(ReaperItem::from_media_file(part, session, mapping).at_position(offset / rate))
+            });
+        let items = track.items
+            .into_iter()
+            .map(|item| {
+                let start = position + item.position;
+                item.with_playrate(rate).at_position(start)
+            })
+            .collect();
+        Self { items, ..track }
+    }
+
+    /// track playing only some channels of the file, named after them
+    pub fn for_channels(media_file: &MediaFile, session: &str, mapping: ChannelMapping) -> Self {
+        let content = media_file.content_hash().unwrap_or_else(|| String::from(media_file.full_path()));
+        let channels = media_file.channels().unwrap_or(2);
+        let name = match mapping.label(&media_file.channel_names()) {
+            Some(label) => format!("{} {}", media_file.filename(), label),
+            None => media_file.filename(),
+        };
+        Self::empty(channel_guid(session, &content, "track", mapping), name)
+            .with_item(ReaperItem::from_media_file(media_file, session, mapping))
+            .with_channels(mapping.width(channels))
     }
-}
 
-impl ReaperTrack {
     pub fn new(
         track_id: String,
         track_name: String,
@@ -73,394 +395,458 @@ impl ReaperTrack {
         item_id: String,
         media_type: MediaType,
     ) -> Self {
-        Self {
-//            track_id: String::from("45571899-9E84-5151-B211-B16E459C356D"),
-//            track_name: String::from("BE FOUND SOON_m1.1"),
-//            track_file_name: String::from("BE FOUND SOON_m1.1.wav"),
-//            track_path: String::from("/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"),
-//            track_length: String::from("206.69117913832199"),
-//            another_id: String::from("007E36C0-EC22-CB19-1344-D87A1BAB7798"),
-//            item_id: String::from("88564052-9129-3D60-867F-7840E1CD613E"),
-
-            track_id,
-            track_name,
+        Self::empty(track_id, track_name).with_item(ReaperItem::new(
+            item_id,
+            another_id,
             track_file_name,
             track_path,
             track_length,
-            another_id,
-            item_id,
             media_type,
+        ))
+    }
+
+    /// track without items, eg. a folder
+    pub fn empty(track_id: String, track_name: String) -> Self {
+        Self { track_id, track_name, items: vec![], channels: 2, folder: FolderState::Track, color: None }
+    }
+
+    pub fn with_item(mut self, item: ReaperItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// reaper tracks carry an even number of channels, so 1 becomes 2 and 5 becomes 6
+    pub fn with_channels(mut self, channels: u16) -> Self {
+        self.channels = channels.max(1).div_ceil(2) * 2;
+        self
+    }
+
+    pub fn with_folder(mut self, folder: FolderState) -> Self {
+        self.folder = folder;
+        self
+    }
+
+    pub fn with_color(mut self, color: Option<u32>) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// places the track's items `seconds` after the start of the project
+    pub fn at_position(mut self, seconds: f64) -> Self {
+        self.items = self.items.into_iter().map(|i| i.at_position(seconds)).collect();
+        self
+    }
+
+    /// refers to the media relative to `directory`, the one the project is saved in, where it can
+    pub fn with_relative_paths(mut self, directory: &Path) -> Self {
+        for item in self.items.iter_mut() {
+            item.path = media_path(&item.path, directory);
+        }
+        self
+    }
+
+    /// places the items at `seconds`, cutting off the part that would play before the start of the project
+    pub fn starting_at(mut self, seconds: f64) -> Self {
+        self.items = self.items
+            .into_iter()
+            .map(|i| match seconds < 0. {
+                true => i.skipping(-seconds).at_position(0.),
+                false => i.at_position(seconds),
+            })
+            .collect();
+        self
+    }
+
+    pub fn in_group(mut self, group: Option<u32>) -> Self {
+        self.items = self.items.into_iter().map(|i| i.in_group(group)).collect();
+        self
+    }
+
+    pub fn as_node(&self) -> RppNode {
+        RppNode::chunk("TRACK")
+            .param(format!("{{{}}}", self.track_id))
+            .child(RppNode::line("NAME").quoted(&self.track_name))
+            .child(RppNode::line("PEAKCOL").param(self.color.unwrap_or(16576)))
+            .child(RppNode::line("BEAT").param(-1))
+            .child(RppNode::line("AUTOMODE").param(0))
+            .child(RppNode::line("VOLPAN").params(&[1, 0, -1, -1, 1]))
+            .child(RppNode::line("MUTESOLO").params(&[0, 0, 0]))
+            .child(RppNode::line("IPHASE").param(0))
+            .child(RppNode::line("ISBUS").params(&self.folder.isbus()))
+            .child(RppNode::line("BUSCOMP").params(&[0, 0]))
+            .child(RppNode::line("SHOWINMIX").params(&["1", "0.6667", "0.5", "1", "0.5", "0", "0", "0"]))
+            .child(RppNode::line("FREEMODE").param(0))
+            .child(RppNode::line("SEL").param(0))
+            .child(RppNode::line("REC").params(&[0, 0, 0, 0, 0, 0, 0]))
+            .child(RppNode::line("VU").param(2))
+            .child(RppNode::line("TRACKHEIGHT").params(&[0, 0, 0]))
+            .child(RppNode::line("INQ").params(&["0", "0", "0", "0.5", "100", "0", "0", "100"]))
+            .child(RppNode::line("NCHAN").param(self.channels))
+            .child(RppNode::line("FX").param(1))
+            .child(RppNode::line("TRACKID").param(format!("{{{}}}", self.track_id)))
+            .child(RppNode::line("PERF").param(0))
+            .child(RppNode::line("MIDIOUT").param(-1))
+            .child(RppNode::line("MAINSEND").params(&[1, 0]))
+            .with_children(self.item_nodes())
+    }
+
+    pub(crate) fn item_nodes(&self) -> Vec<RppNode> {
+        self.items.iter().map(|i| i.as_node()).collect()
+    }
+}
+
+pub struct ReaperItem {
+    item_id: String,
+    take_id: String,
+    file_name: String,
+    path: String,
+    length: String,
+    media_type: MediaType,
+    position: f64,
+    group: Option<u32>,
+    channels: ChannelMapping,
+    /// item volume, 1 being unity
+    volume: f64,
+    playrate: f64,
+    /// seconds into the file the item starts playing at
+    source_offset: f64,
+}
+
+impl ReaperItem {
+    pub fn from_media_file(media_file: &MediaFile, session: &str, mapping: ChannelMapping) -> Self {
+        let content = media_file.content_hash().unwrap_or_else(|| String::from(media_file.full_path()));
+        Self::new(
+            channel_guid(session, &content, "item", mapping),
+            channel_guid(session, &content, "take", mapping),
+            media_file.filename(),
+            String::from(media_file.full_path()),
+            media_file.duration().map(seconds).unwrap_or(0.).to_string(),
+            media_file.media_type().expect("Reaper requires media type specifying"),
+        ).with_channel_mapping(mapping)
+    }
+
+    pub fn new(
+        item_id: String,
+        take_id: String,
+        file_name: String,
+        path: String,
+        length: String,
+        media_type: MediaType,
+    ) -> Self {
+        Self { item_id, take_id, file_name, path, length, media_type, position: 0., group: None, channels: ChannelMapping::All, volume: 1., playrate: 1., source_offset: 0. }
+    }
+
+    pub fn with_channel_mapping(mut self, channels: ChannelMapping) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    /// take channel mode: 3 and up pick a single channel, 67 and up a pair starting at that channel
+    fn channel_mode(&self) -> u16 {
+        match self.channels {
+            ChannelMapping::All => 0,
+            ChannelMapping::Mono(channel) => channel + 2,
+            ChannelMapping::Pair(channel) => channel + 66,
         }
     }
 
+    pub fn at_position(mut self, seconds: f64) -> Self {
+        self.position = seconds;
+        self
+    }
+
+    /// starts playing `seconds` into the file, the item gets that much shorter
+    pub fn skipping(mut self, seconds: f64) -> Self {
+        if let Ok(length) = self.length.parse::<f64>() {
+            self.length = (length - seconds / self.playrate).max(0.).to_string();
+        }
+        self.source_offset += seconds;
+        self
+    }
+
+    /// items sharing a group are selected and moved together
+    pub fn in_group(mut self, group: Option<u32>) -> Self {
+        self.group = group;
+        self
+    }
+
+    /// plays the source `rate` times as fast, the item gets shorter or longer to match
+    pub fn with_playrate(mut self, rate: f64) -> Self {
+        if rate != self.playrate {
+            if let Ok(length) = self.length.parse::<f64>() {
+                self.length = (length * self.playrate / rate).to_string();
+            }
+            self.playrate = rate;
+        }
+        self
+    }
+
+    pub fn with_gain(mut self, gain_db: f64) -> Self {
+        self.volume = 10f64.powf(gain_db / 20.);
+        self
+    }
+
     fn media_type_str(&self) -> String {
         match self.media_type {
             MediaType::Audio => String::from("WAVE"),
             MediaType::Video => String::from("VIDEO"),
         }
     }
+
+    fn as_node(&self) -> RppNode {
+        let group = self.group.map(|g| RppNode::line("GROUP").param(g));
+        RppNode::chunk("ITEM")
+            .child(RppNode::line("POSITION").param(self.position))
+            .child(RppNode::line("SNAPOFFS").param(0))
+            .child(RppNode::line("LENGTH").param(&self.length))
+            .child(RppNode::line("LOOP").param(1))
+            .child(RppNode::line("ALLTAKES").param(0))
+            .child(RppNode::line("FADEIN").params(&["1", "0.01", "0", "1", "0", "0"]))
+            .child(RppNode::line("FADEOUT").params(&["1", "0.01", "0", "1", "0", "0"]))
+            .child(RppNode::line("MUTE").param(0))
+            .child(RppNode::line("SEL").param(0))
+            .with_children(group)
+            .child(RppNode::line("IGUID").param(format!("{{{}}}", self.item_id)))
+            .child(RppNode::line("IID").param(1))
+            .child(RppNode::line("NAME").quoted(&self.file_name))
+            .child(RppNode::line("VOLPAN").param(self.volume).params(&[0, 1, -1]))
+            .child(RppNode::line("SOFFS").param(self.source_offset))
+            .child(RppNode::line("PLAYRATE").param(self.playrate).params(&["1", "0", "-1", "0", "0.0025"]))
+            .child(RppNode::line("CHANMODE").param(self.channel_mode()))
+            .child(RppNode::line("GUID").param(format!("{{{}}}", self.take_id)))
+            .child(
+                RppNode::chunk("SOURCE")
+                    .param(self.media_type_str())
+                    .child(RppNode::line("FILE").quoted(&self.path))
+            )
+    }
 }
 
 impl Track for ReaperTrack {
     fn as_string(&self) -> String {
-        reaper_track(
-//            String::from("45571899-9E84-5151-B211-B16E459C356D"),
-//            String::from("BE FOUND SOON_m1.1"),
-//            String::from("BE FOUND SOON_m1.1.wav"),
-//            String::from("/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"),
-//            String::from("206.69117913832199"),
-//            String::from("007E36C0-EC22-CB19-1344-D87A1BAB7798"),
-//            String::from("88564052-9129-3D60-867F-7840E1CD613E"),
-            self.track_id.clone(),
-            self.track_name.clone(),
-            self.track_file_name.clone(),
-            self.track_path.clone(),
-            self.track_length.clone(),
-            self.another_id.clone(),
-            self.item_id.clone(),
-            self.media_type_str()
-        )
+        self.as_node().to_string()
     }
 }
 
-pub(crate) const REAPER_EXAMPLE: &'static str = r#"\
-<REAPER_PROJECT 0.1 "5.983/linux64" 1578133917
-  RIPPLE 0
-  GROUPOVERRIDE 0 0 0
-  AUTOXFADE 1
-  ENVATTACH 1
-  POOLEDENVATTACH 0
-  MIXERUIFLAGS 11 48
-  PEAKGAIN 1
-  FEEDBACK 0
-  PANLAW 1
-  PROJOFFS 0 0 0
-  MAXPROJLEN 0 600
-  GRID 3199 8 1 8 1 0 0 0
-  TIMEMODE 1 5 -1 30 0 0 -1
-  VIDEO_CONFIG 0 0 256
-  PANMODE 3
-  CURSOR 56
-  ZOOM 1.1747815778626 0 0
-  VZOOMEX 6
-  USE_REC_CFG 0
-  RECMODE 1
-  SMPTESYNC 0 30 100 40 1000 300 0 0 1 0 0
-  LOOP 0
-  LOOPGRAN 0 4
-  RECORD_PATH "" ""
-  <RECORD_CFG
-  >
-  <APPLYFX_CFG
-  >
-  RENDER_FILE ""
-  RENDER_PATTERN ""
-  RENDER_FMT 0 2 0
-  RENDER_1X 0
-  RENDER_RANGE 1 0 0 18 1000
-  RENDER_RESAMPLE 3 0 1
-  RENDER_ADDTOPROJ 0
-  RENDER_STEMS 0
-  RENDER_DITHER 0
-  TIMELOCKMODE 1
-  TEMPOENVLOCKMODE 1
-  ITEMMIX 0
-  DEFPITCHMODE 589824 0
-  TAKELANE 1
-  SAMPLERATE 44100 0 0
-  <RENDER_CFG
-  >
-  LOCK 1
-  <METRONOME 6 2
-    VOL 0.25 0.125
-    FREQ 800 1600 1
-    BEATLEN 4
-    SAMPLES "" ""
-    PATTERN 2863311530 2863311529
-  >
-  GLOBAL_AUTO -1
-  TEMPO 120 4 4
-  PLAYRATE 1 0 0.25 4
-  SELECTION 0 0
-  SELECTION2 0 0
-  MASTERAUTOMODE 0
-  MASTERTRACKHEIGHT 0 0
-  MASTERPEAKCOL 16576
-  MASTERMUTESOLO 0
-  MASTERTRACKVIEW 0 0.6667 0.5 0.5 0 0 0
-  MASTERHWOUT 0 0 1 0 0 0 0 -1
-  MASTER_NCH 2 2
-  MASTER_VOLUME 1 0 -1 -1 1
-  MASTER_FX 1
-  MASTER_SEL 0
-  <MASTERPLAYSPEEDENV
-    ACT 0 -1
-    VIS 0 1 1
-    LANEHEIGHT 0 0
-    ARM 0
-    DEFSHAPE 0 -1 -1
-  >
-  <TEMPOENVEX
-    ACT 0 -1
-    VIS 1 0 1
-    LANEHEIGHT 0 0
-    ARM 0
-    DEFSHAPE 1 -1 -1
-  >
-  <PROJBAY
-  >
-  <TRACK {45571899-9E84-5151-B211-B16E459C356D}
-    NAME "BE FOUND SOON_m1.1"
-    PEAKCOL 16576
-    BEAT -1
-    AUTOMODE 0
-    VOLPAN 1 0 -1 -1 1
-    MUTESOLO 0 0 0
-    IPHASE 0
-    ISBUS 0 0
-    BUSCOMP 0 0
-    SHOWINMIX 1 0.6667 0.5 1 0.5 0 0 0
-    FREEMODE 0
-    SEL 0
-    REC 0 0 0 0 0 0 0
-    VU 2
-    TRACKHEIGHT 0 0 0
-    INQ 0 0 0 0.5 100 0 0 100
-    NCHAN 2
-    FX 1
-    TRACKID {45571899-9E84-5151-B211-B16E459C356D}
-    PERF 0
-    MIDIOUT -1
-    MAINSEND 1 0
-    <ITEM
-      POSITION 0
-      SNAPOFFS 0
-      LENGTH 206.69117913832199
-      LOOP 1
-      ALLTAKES 0
-      FADEIN 1 0.01 0 1 0 0
-      FADEOUT 1 0.01 0 1 0 0
-      MUTE 0
-      SEL 0
-      IGUID {88564052-9129-3D60-867F-7840E1CD613E}
-      IID 1
-      NAME "BE FOUND SOON_m1.1.wav"
-      VOLPAN 1 0 1 -1
-      SOFFS 0
-      PLAYRATE 1 1 0 -1 0 0.0025
-      CHANMODE 0
-      GUID {007E36C0-EC22-CB19-1344-D87A1BAB7798}
-      <SOURCE WAVE
-        FILE "/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"
-      >
-    >
-  >
->
-"#;
-
-pub(crate) fn reaper_project_file(tracks: Vec<String>, project_id: String) -> String {
-    format!(r#"\
-<REAPER_PROJECT 0.1 "5.983/linux64" {project_id}
-  RIPPLE 0
-  GROUPOVERRIDE 0 0 0
-  AUTOXFADE 1
-  ENVATTACH 1
-  POOLEDENVATTACH 0
-  MIXERUIFLAGS 11 48
-  PEAKGAIN 1
-  FEEDBACK 0
-  PANLAW 1
-  PROJOFFS 0 0 0
-  MAXPROJLEN 0 600
-  GRID 3199 8 1 8 1 0 0 0
-  TIMEMODE 1 5 -1 30 0 0 -1
-  VIDEO_CONFIG 0 0 256
-  PANMODE 3
-  CURSOR 56
-  ZOOM 1.1747815778626 0 0
-  VZOOMEX 6
-  USE_REC_CFG 0
-  RECMODE 1
-  SMPTESYNC 0 30 100 40 1000 300 0 0 1 0 0
-  LOOP 0
-  LOOPGRAN 0 4
-  RECORD_PATH "" ""
-  <RECORD_CFG
-  >
-  <APPLYFX_CFG
-  >
-  RENDER_FILE ""
-  RENDER_PATTERN ""
-  RENDER_FMT 0 2 0
-  RENDER_1X 0
-  RENDER_RANGE 1 0 0 18 1000
-  RENDER_RESAMPLE 3 0 1
-  RENDER_ADDTOPROJ 0
-  RENDER_STEMS 0
-  RENDER_DITHER 0
-  TIMELOCKMODE 1
-  TEMPOENVLOCKMODE 1
-  ITEMMIX 0
-  DEFPITCHMODE 589824 0
-  TAKELANE 1
-  SAMPLERATE 44100 0 0
-  <RENDER_CFG
-  >
-  LOCK 1
-  <METRONOME 6 2
-    VOL 0.25 0.125
-    FREQ 800 1600 1
-    BEATLEN 4
-    SAMPLES "" ""
-    PATTERN 2863311530 2863311529
-  >
-  GLOBAL_AUTO -1
-  TEMPO 120 4 4
-  PLAYRATE 1 0 0.25 4
-  SELECTION 0 0
-  SELECTION2 0 0
-  MASTERAUTOMODE 0
-  MASTERTRACKHEIGHT 0 0
-  MASTERPEAKCOL 16576
-  MASTERMUTESOLO 0
-  MASTERTRACKVIEW 0 0.6667 0.5 0.5 0 0 0
-  MASTERHWOUT 0 0 1 0 0 0 0 -1
-  MASTER_NCH 2 2
-  MASTER_VOLUME 1 0 -1 -1 1
-  MASTER_FX 1
-  MASTER_SEL 0
-  <MASTERPLAYSPEEDENV
-    ACT 0 -1
-    VIS 0 1 1
-    LANEHEIGHT 0 0
-    ARM 0
-    DEFSHAPE 0 -1 -1
-  >
-  <TEMPOENVEX
-    ACT 0 -1
-    VIS 1 0 1
-    LANEHEIGHT 0 0
-    ARM 0
-    DEFSHAPE 1 -1 -1
-  >
-  <PROJBAY
-  >{tracks}
->
-"#, tracks = tracks.join("\n"), project_id = project_id).to_string()
+/// item of an existing project that plays one of the scanned files
+struct KnownItem {
+    track: usize,
+    file_name: String,
+    /// the file, relative paths taken from the project's directory
+    path: PathBuf,
+    /// quick hash of the file when it's where the project says
+    hash: Option<String>,
+    position: f64,
+    source_offset: f64,
 }
 
-pub(crate) const REAPER_TRACK_EXAMPLE: &'static str = r#"
-  <TRACK {45571899-9E84-5151-B211-B16E459C356D}
-    NAME "BE FOUND SOON_m1.1"
-    PEAKCOL 16576
-    BEAT -1
-    AUTOMODE 0
-    VOLPAN 1 0 -1 -1 1
-    MUTESOLO 0 0 0
-    IPHASE 0
-    ISBUS 0 0
-    BUSCOMP 0 0
-    SHOWINMIX 1 0.6667 0.5 1 0.5 0 0 0
-    FREEMODE 0
-    SEL 0
-    REC 0 0 0 0 0 0 0
-    VU 2
-    TRACKHEIGHT 0 0 0
-    INQ 0 0 0 0.5 100 0 0 100
-    NCHAN 2
-    FX 1
-    TRACKID {45571899-9E84-5151-B211-B16E459C356D}
-    PERF 0
-    MIDIOUT -1
-    MAINSEND 1 0
-    <ITEM
-      POSITION 0
-      SNAPOFFS 0
-      LENGTH 206.69117913832199
-      LOOP 1
-      ALLTAKES 0
-      FADEIN 1 0.01 0 1 0 0
-      FADEOUT 1 0.01 0 1 0 0
-      MUTE 0
-      SEL 0
-      IGUID {88564052-9129-3D60-867F-7840E1CD613E}
-      IID 1
-      NAME "BE FOUND SOON_m1.1.wav"
-      VOLPAN 1 0 1 -1
-      SOFFS 0
-      PLAYRATE 1 1 0 -1 0 0.0025
-      CHANMODE 0
-      GUID {007E36C0-EC22-CB19-1344-D87A1BAB7798}
-      <SOURCE WAVE
-        FILE "/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"
-      >
-    >
-  >"#;
-
-pub(crate) fn reaper_track(
-    track_id: String,
-    track_name: String,
-    track_file_name: String,
-    track_path: String,
-    track_length: String,
-    another_id: String,
-    item_id: String,
-    media_type: String,
-) -> String {
-    format!(r#"
-  <TRACK {{{track_id}}}
-    NAME "{track_name}"
-    PEAKCOL 16576
-    BEAT -1
-    AUTOMODE 0
-    VOLPAN 1 0 -1 -1 1
-    MUTESOLO 0 0 0
-    IPHASE 0
-    ISBUS 0 0
-    BUSCOMP 0 0
-    SHOWINMIX 1 0.6667 0.5 1 0.5 0 0 0
-    FREEMODE 0
-    SEL 0
-    REC 0 0 0 0 0 0 0
-    VU 2
-    TRACKHEIGHT 0 0 0
-    INQ 0 0 0 0.5 100 0 0 100
-    NCHAN 2
-    FX 1
-    TRACKID {{{track_id}}}
-    PERF 0
-    MIDIOUT -1
-    MAINSEND 1 0
-    <ITEM
-      POSITION 0
-      SNAPOFFS 0
-      LENGTH {track_length}
-      LOOP 1
-      ALLTAKES 0
-      FADEIN 1 0.01 0 1 0 0
-      FADEOUT 1 0.01 0 1 0 0
-      MUTE 0
-      SEL 0
-      IGUID {{{item_id}}}
-      IID 1
-      NAME "{track_file_name}"
-      VOLPAN 1 0 1 -1
-      SOFFS 0
-      PLAYRATE 1 1 0 -1 0 0.0025
-      CHANMODE 0
-      GUID {{{another_id}}}
-      <SOURCE {media_type}
-        FILE "{track_path}"
-      >
-    >
-  >"#,
-            track_path = track_path,
-            track_name = track_name,
-            track_file_name = track_file_name,
-            item_id = item_id,
-            track_length = track_length,
-            another_id = another_id,
-            track_id = track_id,
-            media_type = media_type,
-    )
-}
\ No newline at end of file
+impl KnownItem {
+    /// whether the item plays `file`, the same path or a copy of it
+    fn plays(&self, file: &MediaFile) -> bool {
+        let canonical = |path: &Path| fs::canonicalize(path).ok();
+        match canonical(&self.path) {
+            Some(known) if Some(&known) == canonical(file.entry.path()).as_ref() => true,
+            _ => self.hash.is_some() && self.hash == file.content_hash(),
+        }
+    }
+}
+
+fn item_source_file(item: &RppNode) -> Option<&str> {
+    let mut source = item.find("SOURCE")?;
+    // sections and other wrapped sources keep the file in a nested SOURCE chunk
+    while let Some(inner) = source.find("SOURCE") {
+        source = inner;
+    }
+    source.find("FILE")?.param_value(0)
+}
+
+fn item_seconds(item: &RppNode, name: &str) -> f64 {
+    item.find(name)
+        .and_then(|n| n.param_value(0))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.)
+}
+
+fn known_items(project: &RppNode, directory: &Path) -> Vec<KnownItem> {
+    project
+        .find_all("TRACK")
+        .enumerate()
+        .flat_map(|(track, node)| node.find_all("ITEM").map(move |item| (track, item)))
+        .filter_map(|(track, item)| {
+            let file = item_source_file(item)?;
+            let path = directory.join(file);
+            Some(KnownItem {
+                track,
+                file_name: file_name_of(file),
+                hash: quick_hash(&path),
+                path,
+                position: item_seconds(item, "POSITION"),
+                source_offset: item_seconds(item, "SOFFS"),
+            })
+        })
+        .collect()
+}
+
+fn file_name_of(path: &str) -> String {
+    String::from(path.rsplit(['/', '\\']).next().unwrap_or(path))
+}
+
+/// appends `media` that overlaps files already used in `project` without touching any of the existing tracks and items.
+/// new files become items on a track holding files of the same family when they fit in there, otherwise a new track is added.
+/// media the project refers to by relative paths is looked for in `directory`, where the project is, and the media added is referred to relative to it.
+/// returns names of the files that were added
+pub fn merge_into(project: &mut RppNode, media: &[MediaFile], directory: &Path) -> Vec<String> {
+    let known = known_items(project, directory);
+    let is_known = |f: &MediaFile| known.iter().any(|k| k.plays(f));
+    let anchors: Vec<&MediaFile> = media.iter().filter(|f| is_known(f)).collect();
+
+    // the project's time zero, derived from the first item we know the recording time of
+    let origin: Option<NaiveDateTime> = anchors.iter().find_map(|file| {
+        let item = known.iter().find(|k| k.plays(file))?;
+        let shift = item.source_offset - item.position;
+        Some(file.start()? + Duration::microseconds((shift * 1_000_000.) as i64))
+    });
+    let origin = match origin {
+        Some(origin) => origin,
+        None => return vec![],
+    };
+
+    // merged items get the ids an export of the session would give them, derived from its video like `from_group` does
+    let session = anchors
+        .iter()
+        .filter(|f| matches!(f.media_type(), Some(MediaType::Video)))
+        .min_by_key(|f| f.start())
+        .map(|video| video.content_hash().unwrap_or_else(|| video.filename()))
+        .or_else(|| project.param_value(2).map(String::from))
+        .unwrap_or_default();
+    let mut added = vec![];
+    for file in media.iter().filter(|f| !is_known(f) && anchors.iter().any(|a| a.overlaps(f))) {
+        let position = match file.offset_from(origin) {
+            Some(offset) => seconds(offset),
+            None => continue,
+        };
+        let end = position + seconds(file.duration().unwrap_or_else(Duration::zero));
+        if end <= 0. {
+            continue;
+        }
+        // what was recorded before the project starts is left out rather than placed before zero
+        let track = ReaperTrack::from_media_file(file, &session)
+            .with_relative_paths(directory)
+            .starting_at(position);
+        let position = position.max(0.);
+        let family = name_family(&file.filename());
+        let target = known
+            .iter()
+            .filter(|k| name_family(&k.file_name) == family)
+            .map(|k| k.track)
+            .find(|&index| {
+                project.find_all("TRACK").nth(index).is_some_and(|t| {
+                    t.find_all("ITEM").all(|i| {
+                        let start = item_seconds(i, "POSITION");
+                        end <= start || start + item_seconds(i, "LENGTH") <= position
+                    })
+                })
+            });
+        match target {
+            Some(index) => {
+                if let Some(existing) = project.children.iter_mut().filter(|c| c.name == "TRACK").nth(index) {
+                    existing.children.extend(track.item_nodes());
+                }
+            },
+            None => project.children.push(track.as_node()),
+        }
+        added.push(file.filename());
+    }
+    added
+}
+
+/// everything in the project chunk that comes before the tracks
+fn project_settings(settings: &ReaperSettings) -> Vec<RppNode> {
+    let fps = settings.fps();
+    // hours:minutes:seconds:frames ruler when there's a video to line up with
+    let time_mode = if settings.frame_rate.is_some() { 5 } else { 1 };
+    let sample_rate = match settings.sample_rate {
+        Some(rate) => RppNode::line("SAMPLERATE").params(&[rate, 1, 0]),
+        None => RppNode::line("SAMPLERATE").params(&[44100, 0, 0]),
+    };
+    vec![
+        RppNode::line("RIPPLE").param(0),
+        RppNode::line("GROUPOVERRIDE").params(&[0, 0, 0]),
+        RppNode::line("AUTOXFADE").param(1),
+        RppNode::line("ENVATTACH").param(1),
+        RppNode::line("POOLEDENVATTACH").param(0),
+        RppNode::line("MIXERUIFLAGS").params(&[11, 48]),
+        RppNode::line("PEAKGAIN").param(1),
+        RppNode::line("FEEDBACK").param(0),
+        RppNode::line("PANLAW").param(1),
+        RppNode::line("PROJOFFS").params(&[0, 0, 0]),
+        RppNode::line("MAXPROJLEN").param(0).param(settings.length.unwrap_or(600.)),
+        RppNode::line("GRID").params(&[3199, 8, 1, 8, 1, 0, 0, 0]),
+        RppNode::line("TIMEMODE").params(&[time_mode, 5, -1]).param(&fps).params(&[0, 0, -1]),
+        RppNode::line("VIDEO_CONFIG").params(&[0, 0, 256]),
+        RppNode::line("PANMODE").param(3),
+        RppNode::line("CURSOR").param(56),
+        RppNode::line("ZOOM").params(&["1.1747815778626", "0", "0"]),
+        RppNode::line("VZOOMEX").param(6),
+        RppNode::line("USE_REC_CFG").param(0),
+        RppNode::line("RECMODE").param(1),
+        RppNode::line("SMPTESYNC").param(0).param(&fps).params(&[100, 40, 1000, 300, 0, 0, 1, 0, 0]),
+        RppNode::line("LOOP").param(0),
+        RppNode::line("LOOPGRAN").params(&[0, 4]),
+        RppNode::line("RECORD_PATH").quoted("").quoted(""),
+        RppNode::chunk("RECORD_CFG"),
+        RppNode::chunk("APPLYFX_CFG"),
+        RppNode::line("RENDER_FILE").quoted(""),
+        RppNode::line("RENDER_PATTERN").quoted(""),
+        RppNode::line("RENDER_FMT").params(&[0, 2, 0]),
+        RppNode::line("RENDER_1X").param(0),
+        RppNode::line("RENDER_RANGE").params(&[1, 0, 0, 18, 1000]),
+        RppNode::line("RENDER_RESAMPLE").params(&[3, 0, 1]),
+        RppNode::line("RENDER_ADDTOPROJ").param(0),
+        RppNode::line("RENDER_STEMS").param(0),
+        RppNode::line("RENDER_DITHER").param(0),
+        RppNode::line("TIMELOCKMODE").param(1),
+        RppNode::line("TEMPOENVLOCKMODE").param(1),
+        RppNode::line("ITEMMIX").param(0),
+        RppNode::line("DEFPITCHMODE").params(&[589824, 0]),
+        RppNode::line("TAKELANE").param(1),
+        sample_rate,
+        RppNode::chunk("RENDER_CFG"),
+        RppNode::line("LOCK").param(1),
+        RppNode::chunk("METRONOME")
+            .params(&[6, 2])
+            .child(RppNode::line("VOL").params(&["0.25", "0.125"]))
+            .child(RppNode::line("FREQ").params(&[800, 1600, 1]))
+            .child(RppNode::line("BEATLEN").param(4))
+            .child(RppNode::line("SAMPLES").quoted("").quoted(""))
+            .child(RppNode::line("PATTERN").params(&[2863311530u32, 2863311529])),
+        RppNode::line("GLOBAL_AUTO").param(-1),
+        RppNode::line("TEMPO").params(&[120, 4, 4]),
+        RppNode::line("PLAYRATE").params(&["1", "0", "0.25", "4"]),
+        RppNode::line("SELECTION").params(&[0, 0]),
+        RppNode::line("SELECTION2").params(&[0, 0]),
+        RppNode::line("MASTERAUTOMODE").param(0),
+        RppNode::line("MASTERTRACKHEIGHT").params(&[0, 0]),
+        RppNode::line("MASTERPEAKCOL").param(16576),
+        RppNode::line("MASTERMUTESOLO").param(0),
+        RppNode::line("MASTERTRACKVIEW").params(&["0", "0.6667", "0.5", "0.5", "0", "0", "0"]),
+        RppNode::line("MASTERHWOUT").params(&[0, 0, 1, 0, 0, 0, 0, -1]),
+        RppNode::line("MASTER_NCH").params(&[2, 2]),
+        RppNode::line("MASTER_VOLUME").params(&[1, 0, -1, -1, 1]),
+        RppNode::line("MASTER_FX").param(1),
+        RppNode::line("MASTER_SEL").param(0),
+        RppNode::chunk("MASTERPLAYSPEEDENV")
+            .child(RppNode::line("ACT").params(&[0, -1]))
+            .child(RppNode::line("VIS").params(&[0, 1, 1]))
+            .child(RppNode::line("LANEHEIGHT").params(&[0, 0]))
+            .child(RppNode::line("ARM").param(0))
+            .child(RppNode::line("DEFSHAPE").params(&[0, -1, -1])),
+        RppNode::chunk("TEMPOENVEX")
+            .child(RppNode::line("ACT").params(&[0, -1]))
+            .child(RppNode::line("VIS").params(&[1, 0, 1]))
+            .child(RppNode::line("LANEHEIGHT").params(&[0, 0]))
+            .child(RppNode::line("ARM").param(0))
+            .child(RppNode::line("DEFSHAPE").params(&[1, -1, -1])),
+        RppNode::chunk("PROJBAY"),
+    ]
+}
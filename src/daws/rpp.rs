@@ -0,0 +1,224 @@
+use std::fmt::{self, Display, Formatter};
+use std::error::Error;
+
+const INDENT: &str = "  ";
+
+/// single parameter of an RPP line, `Quoted` ones are wrapped in quotes when written out
+#[derive(Debug, Clone, PartialEq)]
+pub enum RppParam {
+    Bare(String),
+    Quoted(String),
+}
+
+impl RppParam {
+    pub fn value(&self) -> &str {
+        match self {
+            RppParam::Bare(value) => value,
+            RppParam::Quoted(value) => value,
+        }
+    }
+}
+
+impl Display for RppParam {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RppParam::Bare(value) => write!(f, "{}", value),
+            // reaper picks whichever quote character doesn't appear in the value
+            RppParam::Quoted(value) if !value.contains('"') => write!(f, "\"{}\"", value),
+            RppParam::Quoted(value) if !value.contains('\'') => write!(f, "'{}'", value),
+            RppParam::Quoted(value) => write!(f, "`{}`", value.replace('`', "'")),
+        }
+    }
+}
+
+/// a line (`NAME param param`) or a chunk (`<NAME param ... >`) of a Reaper project
+#[derive(Debug, Clone, PartialEq)]
+pub struct RppNode {
+    pub name: String,
+    pub params: Vec<RppParam>,
+    pub children: Vec<RppNode>,
+    pub chunk: bool,
+}
+
+impl RppNode {
+    pub fn line(name: &str) -> Self {
+        Self { name: String::from(name), params: vec![], children: vec![], chunk: false }
+    }
+
+    pub fn chunk(name: &str) -> Self {
+        Self { chunk: true, ..Self::line(name) }
+    }
+
+    pub fn param<T: ToString>(mut self, value: T) -> Self {
+        self.params.push(RppParam::Bare(value.to_string()));
+        self
+    }
+
+    /// shorthand for several bare parameters, eg. `.params(&[0, 0, 0])`
+    pub fn params<T: ToString>(self, values: &[T]) -> Self {
+        values.iter().fold(self, |node, value| node.param(value.to_string()))
+    }
+
+    pub fn quoted<T: ToString>(mut self, value: T) -> Self {
+        self.params.push(RppParam::Quoted(value.to_string()));
+        self
+    }
+
+    pub fn child(mut self, child: RppNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    pub fn with_children<I: IntoIterator<Item = RppNode>>(mut self, children: I) -> Self {
+        self.children.extend(children);
+        self
+    }
+
+    pub fn param_value(&self, index: usize) -> Option<&str> {
+        Some(self.params.get(index)?.value())
+    }
+
+    /// first direct child called `name`
+    pub fn find(&self, name: &str) -> Option<&RppNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut RppNode> {
+        self.children.iter_mut().find(|c| c.name == name)
+    }
+
+    /// all direct children called `name`
+    pub fn find_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a RppNode> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// replaces the parameters of the first child line called `name`, appending the line if it's missing
+    pub fn set(&mut self, line: RppNode) {
+        match self.children.iter_mut().find(|c| c.name == line.name) {
+            Some(existing) => existing.params = line.params,
+            None => self.children.push(line),
+        }
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = INDENT.repeat(depth);
+        out.push_str(&indent);
+        if self.chunk {
+            out.push('<');
+        }
+        out.push_str(&self.name);
+        for param in &self.params {
+            out.push(' ');
+            out.push_str(&param.to_string());
+        }
+        out.push('\n');
+        if self.chunk {
+            for child in &self.children {
+                child.write(out, depth + 1);
+            }
+            out.push_str(&indent);
+            out.push_str(">\n");
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Self, RppParseError> {
+        let mut stack: Vec<RppNode> = vec![];
+        let mut root = None;
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if root.is_some() {
+                return Err(RppParseError::new(number, "content after the end of the project"));
+            }
+            if line == ">" {
+                let finished = stack.pop().ok_or_else(|| RppParseError::new(number, "unexpected '>'"))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(finished),
+                    None => root = Some(finished),
+                }
+                continue;
+            }
+            let node = parse_line(line);
+            match (node.chunk, stack.last_mut()) {
+                (true, _) => stack.push(node),
+                (false, Some(parent)) => parent.children.push(node),
+                (false, None) => return Err(RppParseError::new(number, "expected a '<' chunk")),
+            }
+        }
+        match (root, stack.is_empty()) {
+            (Some(root), true) => Ok(root),
+            _ => Err(RppParseError::new(source.lines().count(), "unterminated chunk")),
+        }
+    }
+}
+
+impl Display for RppNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write(&mut out, 0);
+        write!(f, "{}", out)
+    }
+}
+
+fn parse_line(line: &str) -> RppNode {
+    let (chunk, line) = match line.strip_prefix('<') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // notes and other free-form text lines are kept verbatim
+    if line.starts_with('|') {
+        return RppNode { chunk, ..RppNode::line(line) };
+    }
+    let mut tokens = tokenize(line).into_iter();
+    let name = tokens.next().map(|t| String::from(t.value())).unwrap_or_default();
+    RppNode { name, params: tokens.collect(), children: vec![], chunk }
+}
+
+fn tokenize(line: &str) -> Vec<RppParam> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '"' || c == '\'' || c == '`' {
+            chars.next();
+            let value: String = chars.by_ref().take_while(|&n| n != c).collect();
+            tokens.push(RppParam::Quoted(value));
+        } else {
+            let mut value = String::new();
+            while let Some(&n) = chars.peek() {
+                if n.is_whitespace() {
+                    break;
+                }
+                value.push(n);
+                chars.next();
+            }
+            tokens.push(RppParam::Bare(value));
+        }
+    }
+    tokens
+}
+
+#[derive(Debug)]
+pub struct RppParseError {
+    line: usize,
+    message: String,
+}
+
+impl RppParseError {
+    fn new(line: usize, message: &str) -> Self {
+        Self { line: line + 1, message: String::from(message) }
+    }
+}
+
+impl Display for RppParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid reaper project (line {}): {}", self.line, self.message)
+    }
+}
+
+impl Error for RppParseError {}
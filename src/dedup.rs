@@ -0,0 +1,110 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::hashing::{full_hash, quick_hash};
+use crate::media_file::MediaFile;
+
+/// groups of indices into `paths` whose files have identical content, in the order of `paths`.
+/// sizes are compared first, the quick hash narrows it down and the full hash confirms
+pub fn identical_groups<T: AsRef<Path>>(paths: &[T]) -> Vec<Vec<usize>> {
+    let narrowed = |indices: Vec<usize>, key: &dyn Fn(&Path) -> Option<String>| {
+        let mut buckets: HashMap<String, Vec<usize>> = HashMap::new();
+        for index in indices {
+            if let Some(key) = key(paths[index].as_ref()) {
+                buckets.entry(key).or_default().push(index);
+            }
+        }
+        buckets.into_values().filter(|b| b.len() > 1).collect::<Vec<_>>()
+    };
+    let size = |path: &Path| Some(fs::metadata(path).ok()?.len().to_string());
+    let quick = |path: &Path| quick_hash(path);
+    let full = |path: &Path| full_hash(path);
+
+    let mut groups: Vec<Vec<usize>> = narrowed((0..paths.len()).collect(), &size)
+        .into_iter()
+        .flat_map(|same_size| narrowed(same_size, &quick))
+        .flat_map(|same_quick| narrowed(same_quick, &full))
+        .collect();
+    for group in groups.iter_mut() {
+        group.sort();
+    }
+    groups.sort();
+    groups
+}
+
+/// collapses identical files into the first one found, which remembers where the others are
+pub fn deduplicate(files: Vec<MediaFile>) -> Vec<MediaFile> {
+    // overlapping directories, eg. /footage and /footage/day1, find the same file more than once
+    let mut seen = HashSet::new();
+    let files: Vec<MediaFile> = files
+        .into_iter()
+        .filter(|f| seen.insert(canonical(f.entry.path())))
+        .collect();
+    let groups = identical_groups(&files.iter().map(|f| f.entry.path()).collect::<Vec<_>>());
+    let mut duplicate_of: BTreeMap<usize, usize> = BTreeMap::new();
+    for group in &groups {
+        for index in &group[1..] {
+            duplicate_of.insert(*index, group[0]);
+        }
+    }
+    let mut files: Vec<Option<MediaFile>> = files.into_iter().map(Some).collect();
+    for (duplicate, original) in &duplicate_of {
+        let path = files[*duplicate].as_ref().map(|f| f.entry.path().to_path_buf());
+        if let (Some(path), Some(original)) = (path, files[*original].as_mut()) {
+            original.add_duplicate(path);
+        }
+    }
+    files
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !duplicate_of.contains_key(index))
+        .filter_map(|(_, file)| file)
+        .collect()
+}
+
+/// `files` once the files at `changed` were added, modified or deleted, probing what's at a path with `probe`.
+/// a file that's gone carries on as a copy of it that's still around.
+/// `files` are already told apart, only what's probed again is compared with them
+pub fn rescanned(files: Vec<MediaFile>, changed: &[&Path], probe: impl Fn(&Path) -> Vec<MediaFile>) -> Vec<MediaFile> {
+    let mut probed: Vec<PathBuf> = changed.iter().map(|p| p.to_path_buf()).collect();
+    let mut kept = vec![];
+    for mut file in files {
+        file.retain_duplicates(|p| !changed.contains(&p));
+        match changed.contains(&file.entry.path()) {
+            true => probed.extend(file.duplicates().iter().cloned()),
+            false => kept.push(file),
+        }
+    }
+    let known: HashSet<PathBuf> = kept.iter().flat_map(|f| f.locations()).map(canonical).collect();
+    let fresh: Vec<MediaFile> = probed
+        .iter()
+        .flat_map(|p| probe(p))
+        .filter(|f| !known.contains(&canonical(f.entry.path())))
+        .collect();
+    for file in deduplicate(fresh) {
+        match kept.iter_mut().find(|k| identical(k, &file)) {
+            Some(original) => {
+                for path in file.locations() {
+                    original.add_duplicate(path.to_path_buf());
+                }
+            },
+            None => kept.push(file),
+        }
+    }
+    kept
+}
+
+/// same size and content hash, which stays cached with the file between rescans, and then the same full hash
+fn identical(one: &MediaFile, other: &MediaFile) -> bool {
+    let size = |f: &MediaFile| fs::metadata(f.entry.path()).ok().map(|m| m.len());
+    size(one).is_some()
+        && size(one) == size(other)
+        && one.content_hash().is_some()
+        && one.content_hash() == other.content_hash()
+        && full_hash(one.entry.path()) == full_hash(other.entry.path())
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -1,8 +1,17 @@
 use crate::media_file::MediaFile;
+use crate::mix::MixSettings;
 use chrono::{NaiveDateTime, Duration};
 
 pub type DeviceDatetimeGetter = fn(&MediaFile) -> Option<NaiveDateTime>; // returns creation time
 
+/// recorder or camera, recognized by the metadata it leaves in its files
+pub struct Device {
+    pub name: &'static str,
+    pub creation_time: DeviceDatetimeGetter,
+    /// how its recordings go into a rendered mixdown unless the mix profiles file or the run says otherwise
+    pub mix: MixSettings,
+}
+
 fn android_10(device: &MediaFile) -> Option<NaiveDateTime> {
     let creation = device
         .ffmpeg_data_raw()?
@@ -30,10 +39,19 @@ fn zoom_h6(device: &MediaFile) -> Option<NaiveDateTime> {
     ).ok()
 }
 
-pub fn device_metadata_parsers() -> Vec<DeviceDatetimeGetter> {
+pub fn devices() -> Vec<Device> {
     vec![
-        android_10,
-        zoom_h6,
-        filesystem, // this should go last as it's the least informative one (works only for media created on your machine)
+        Device { name: "android", creation_time: android_10, mix: MixSettings::default() },
+        Device { name: "zoom-h6", creation_time: zoom_h6, mix: MixSettings::default() },
+        Device { name: "unknown", creation_time: filesystem, mix: MixSettings::default() }, // this should go last as it's the least informative one (works only for media created on your machine)
     ]
-}
\ No newline at end of file
+}
+
+/// mix settings of the device profile called `name`
+pub fn device_mix(name: &str) -> MixSettings {
+    devices().into_iter().find(|d| d.name == name).map(|d| d.mix).unwrap_or_default()
+}
+
+pub fn device_metadata_parsers() -> Vec<DeviceDatetimeGetter> {
+    devices().iter().map(|d| d.creation_time).collect()
+}
@@ -1,5 +1,6 @@
 use std::path::{Path};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::io::{ErrorKind, Read};
 use std::ffi::OsStr;
 use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
@@ -7,6 +8,8 @@ use std::convert::{TryFrom, TryInto};
 use serde::{Serialize, Deserialize};
 use serde_json::{Result as SerdeResult};
 
+use crate::loudness::{Loudness, LOUDNORM_ANALYSIS};
+
 static FPROBE_COMMAND: (&'static str, [&'static str; 6]) = ("ffprobe", ["-v", "quiet", "-print_format", "json", "-show_format", "-show_streams"]);
 static VERSION_COMMAND: (&'static str, [&'static str; 1]) = ("ffprobe", ["-version"]);
 
@@ -32,6 +35,67 @@ impl Ffmpeg {
         Some(String::from(version_num))
     }
 
+    /// decodes the file into signed 16 bit mono samples at `sample_rate`, handed to `consume` a piece at a time
+    /// so hours of audio never have to fit in memory
+    pub fn stream_mono_pcm<T: AsRef<Path>>(file: T, sample_rate: u32, mut consume: impl FnMut(&[i16])) -> Option<()> {
+        let mut child = Command::new("ffmpeg")
+            .args(["-v", "quiet", "-i"])
+            .arg(file.as_ref())
+            .args(["-ac", "1", "-ar", &sample_rate.to_string(), "-f", "s16le", "-"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+        let mut stdout = child.stdout.take()?;
+        let mut buffer = vec![0u8; 1 << 16];
+        let mut filled = 0;
+        loop {
+            match stdout.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    let _ = child.kill();
+                    return None
+                },
+            }
+            // a sample can be split between two reads, its first byte waits for the next one
+            let whole = filled - filled % 2;
+            let samples: Vec<i16> = buffer[..whole].chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+            consume(&samples);
+            buffer.copy_within(whole..filled, 0);
+            filled -= whole;
+        }
+        match child.wait().ok()?.success() {
+            true => Some(()),
+            false => None,
+        }
+    }
+
+    /// runs ffmpeg with `args`, letting it print its progress to the terminal
+    pub fn run<S: AsRef<OsStr>>(args: &[S]) -> Option<()> {
+        let status = Command::new("ffmpeg").args(args).status().ok()?;
+        match status.success() {
+            true => Some(()),
+            false => None,
+        }
+    }
+
+    /// runs ffmpeg with `args` quietly and returns what it printed to stderr, where its analysis filters report
+    pub fn analyze<S: AsRef<OsStr>>(args: &[S]) -> Option<String> {
+        let output = Command::new("ffmpeg").args(["-hide_banner", "-nostats"]).args(args).output().ok()?;
+        match output.status.success() {
+            true => Some(String::from_utf8_lossy(&output.stderr).into_owned()),
+            false => None,
+        }
+    }
+
+    /// EBU R128 loudness of the file's audio
+    pub fn loudness<T: AsRef<Path>>(file: T) -> Option<Loudness> {
+        let file = file.as_ref().to_str()?;
+        let output = Self::analyze(&["-i", file, "-af", LOUDNORM_ANALYSIS, "-vn", "-f", "null", "-"])?;
+        Loudness::from_loudnorm_output(&output)
+    }
+
     pub fn media_file_metadata_raw<T: AsRef<Path>>(file: T) -> Option<FfmpegFileData> {
         let (base, args) = FPROBE_COMMAND;
         let mut args = args.to_vec();
@@ -58,6 +122,8 @@ pub struct RawStream {
     pub sample_rate: Option<String>, // "44100"
     pub channels: Option<u16>,
     pub bits_per_sample: Option<u16>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
     pub r_frame_rate: Option<String>,
     pub avg_frame_rate: Option<String>,
     pub time_base: Option<String>,
@@ -66,6 +132,8 @@ pub struct RawStream {
     pub bit_rate: Option<String>, // "2116800"
     pub bits_per_raw_sample: Option<String>, // "24"
     pub disposition: HashMap<String, u8>,
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -96,6 +164,10 @@ pub struct RawFormatTags {
     #[serde(rename = "com.android.capture.fps")]
     pub com_android_capture_fps: Option<String>,
     pub major_brand: Option<String>,
+    /// `HH:MM:SS:FF` of the first frame, where the container keeps it
+    pub timecode: Option<String>,
+    /// samples since midnight of the first sample, from the BWF chunk of wave files
+    pub time_reference: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
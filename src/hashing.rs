@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::fmt::Write;
+
+use sha2::{Sha256, Digest};
+
+/// how much of the beginning and the end of a file goes into the quick hash
+const QUICK_HASH_CHUNK: u64 = 1024 * 1024;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::with_capacity(bytes.len() * 2), |mut out, b| {
+        let _ = write!(out, "{:02x}", b);
+        out
+    })
+}
+
+/// sha256 of the file size together with its first and last megabyte.
+/// cheap enough to run on every scanned file and good enough to tell recordings apart
+pub fn quick_hash<T: AsRef<Path>>(path: T) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let size = file.metadata().ok()?.len();
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    let mut head = vec![];
+    file.by_ref().take(QUICK_HASH_CHUNK).read_to_end(&mut head).ok()?;
+    hasher.update(&head);
+
+    if size > QUICK_HASH_CHUNK * 2 {
+        file.seek(SeekFrom::End(-(QUICK_HASH_CHUNK as i64))).ok()?;
+        let mut tail = vec![];
+        file.take(QUICK_HASH_CHUNK).read_to_end(&mut tail).ok()?;
+        hasher.update(&tail);
+    }
+    Some(hex(&hasher.finalize()))
+}
+
+/// sha256 of the whole file, read in chunks so large recordings don't have to fit in memory
+pub fn full_hash<T: AsRef<Path>>(path: T) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 4 * 1024 * 1024];
+    loop {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Some(hex(&hasher.finalize()))
+}
+
+/// deterministic GUID (eg. `45571899-9E84-5151-B211-B16E459C356D`) derived from `parts`
+pub fn guid(parts: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part.as_bytes());
+        hasher.update([0u8]); // keeps ["ab", "c"] and ["a", "bc"] apart
+    }
+    let digest = hex(&hasher.finalize()[..16]).to_uppercase();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &digest[0..8],
+        &digest[8..12],
+        &digest[12..16],
+        &digest[16..20],
+        &digest[20..32],
+    )
+}
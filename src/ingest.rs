@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::clip::Clip;
+use crate::hashing::{full_hash, quick_hash};
+use crate::media_file::{MediaFile, media_files};
+
+/// where ingested files go below the destination, `{date}`, `{device}` and `{session}` are filled in per file
+pub const DEFAULT_LAYOUT: &str = "{date}/{device}/{session}";
+
+const COPY_CHUNK: usize = 8 * 1024 * 1024;
+/// how many `-2`, `-3`... suffixes are tried before giving up on a name
+const MAX_NAME_ATTEMPTS: usize = 1000;
+
+/// brings scanned media somewhere safe before projects reference it, or leaves it where it is
+#[derive(Debug, Clone)]
+pub struct Ingest {
+    /// `None` references the files in place
+    destination: Option<PathBuf>,
+    layout: String,
+}
+
+/// what a group of files is filed under
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub date: String,
+    pub name: String,
+}
+
+impl Session {
+    /// sessions are named after their video and dated by its start
+    pub fn of(video: &Clip) -> Self {
+        Self {
+            date: video.start().map(|s| s.format("%Y-%m-%d").to_string()).unwrap_or_else(|| String::from("undated")),
+            name: video.first().base_name().map(|b| String::from(b.trim_end_matches('.'))).unwrap_or_else(|| video.filename()),
+        }
+    }
+}
+
+impl Ingest {
+    pub fn in_place() -> Self {
+        Self { destination: None, layout: String::from(DEFAULT_LAYOUT) }
+    }
+
+    pub fn copying_to(destination: PathBuf, layout: &str) -> Self {
+        Self { destination: Some(destination), layout: String::from(layout) }
+    }
+
+    /// directory `file` is copied into, `None` when referencing in place
+    pub fn directory_for(&self, file: &MediaFile, session: &Session) -> Option<PathBuf> {
+        let destination = self.destination.as_ref()?;
+        Some(destination.join(render_layout(&self.layout, &session.date, file.device().unwrap_or("unknown"), &session.name)))
+    }
+
+    pub fn ingest_file(&self, file: &MediaFile, session: &Session) -> Result<MediaFile, IngestError> {
+        let directory = match self.directory_for(file, session) {
+            Some(directory) => directory,
+            None => return Ok(file.clone()),
+        };
+        fs::create_dir_all(&directory).map_err(|e| IngestError::io(&directory, e))?;
+        let target = free_name(&directory, file.entry.path())?;
+        if !target.exists() {
+            copy_verified(file.entry.path(), &target, true)?;
+        }
+        media_files(&target)
+            .into_iter()
+            .next()
+            .ok_or_else(|| IngestError::new(&target, "copied file is not readable media"))
+    }
+
+    pub fn ingest(&self, clip: &Clip, session: &Session) -> Result<Clip, IngestError> {
+        let parts = clip.parts.iter().map(|p| self.ingest_file(p, session)).collect::<Result<_, _>>()?;
+        Ok(clip.with_parts(parts))
+    }
+}
+
+/// fills in the layout, keeping every value a single path component
+pub fn render_layout(layout: &str, date: &str, device: &str, session: &str) -> PathBuf {
+    let component = |value: &str| value.replace(['/', '\\'], "_");
+    PathBuf::from(
+        layout
+            .replace("{date}", &component(date))
+            .replace("{device}", &component(device))
+            .replace("{session}", &component(session))
+    )
+}
+
+/// name for `source` in `directory`: its own file name, unless a different file already took it.
+/// files from different cards often share names (VID_0001.mp4), those get `-2`, `-3`... suffixes
+pub fn free_name(directory: &Path, source: &Path) -> Result<PathBuf, IngestError> {
+    let name = source.file_name().ok_or_else(|| IngestError::new(source, "not a file"))?;
+    let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("file");
+    let extension = source.extension().and_then(|e| e.to_str());
+    let source_hash = quick_hash(source).ok_or_else(|| IngestError::new(source, "can't be read"))?;
+    for attempt in 1..=MAX_NAME_ATTEMPTS {
+        let candidate = match (attempt, extension) {
+            (1, _) => directory.join(name),
+            (n, Some(extension)) => directory.join(format!("{}-{}.{}", stem, n, extension)),
+            (n, None) => directory.join(format!("{}-{}", stem, n)),
+        };
+        // the same recording copied earlier is reused rather than copied again
+        if !candidate.exists() || same_content(&candidate, source, &source_hash) {
+            return Ok(candidate)
+        }
+    }
+    Err(IngestError::new(source, &format!("{} files by its name are in {} already", MAX_NAME_ATTEMPTS, directory.display())))
+}
+
+/// whether `copy` holds what `source` does, the quick hash rules out most files before the whole of both is read
+fn same_content(copy: &Path, source: &Path, source_hash: &str) -> bool {
+    quick_hash(copy).as_deref() == Some(source_hash) && full_hash(copy).is_some_and(|hash| full_hash(source) == Some(hash))
+}
+
+/// copies through a `.part` file that is picked up where it stopped when the copy gets interrupted,
+/// then compares full checksums before the copy takes the target name
+pub fn copy_verified(source: &Path, target: &Path, progress: bool) -> Result<(), IngestError> {
+    let partial = partial_path(target);
+    let size = fs::metadata(source).map_err(|e| IngestError::io(source, e))?.len();
+    let mut done = fs::metadata(&partial).map(|m| m.len()).unwrap_or(0);
+    if done > size {
+        done = 0;
+    }
+    let mut input = File::open(source).map_err(|e| IngestError::io(source, e))?;
+    input.seek(SeekFrom::Start(done)).map_err(|e| IngestError::io(source, e))?;
+    let mut output = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&partial)
+        .map_err(|e| IngestError::io(&partial, e))?;
+    output.set_len(done).and_then(|_| output.seek(SeekFrom::Start(done))).map_err(|e| IngestError::io(&partial, e))?;
+
+    let name = source.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    if progress && done > 0 {
+        println!("# resuming {} at {}", name, mebibytes(done));
+    }
+    let mut buffer = vec![0u8; COPY_CHUNK];
+    let mut reported = None;
+    loop {
+        let read = input.read(&mut buffer).map_err(|e| IngestError::io(source, e))?;
+        if read == 0 {
+            break;
+        }
+        output.write_all(&buffer[..read]).map_err(|e| IngestError::io(&partial, e))?;
+        done += read as u64;
+        let percent = done * 100 / size.max(1);
+        if progress && reported != Some(percent) {
+            print!("\r# copying {} {:>3}% ({} of {})", name, percent, mebibytes(done), mebibytes(size));
+            let _ = io::stdout().flush();
+            reported = Some(percent);
+        }
+    }
+    if progress {
+        println!();
+    }
+    output.sync_all().map_err(|e| IngestError::io(&partial, e))?;
+
+    if full_hash(source) != full_hash(&partial) {
+        // whatever is in the partial file can't be trusted to resume from
+        let _ = fs::remove_file(&partial);
+        return Err(IngestError::new(target, "checksum mismatch after copying"))
+    }
+    fs::rename(&partial, target).map_err(|e| IngestError::io(target, e))
+}
+
+fn partial_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+fn mebibytes(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024. * 1024.))
+}
+
+#[derive(Debug)]
+pub struct IngestError {
+    path: PathBuf,
+    message: String,
+}
+
+impl IngestError {
+    fn new(path: &Path, message: &str) -> Self {
+        Self { path: path.to_path_buf(), message: String::from(message) }
+    }
+
+    fn io(path: &Path, error: io::Error) -> Self {
+        Self::new(path, &error.to_string())
+    }
+}
+
+impl Display for IngestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "ingesting {} failed: {}", self.path.display(), self.message)
+    }
+}
+
+impl Error for IngestError {}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// RF64 files put the real size of chunks bigger than 4GB into the ds64 chunk
+const RF64_SIZE_PLACEHOLDER: u32 = 0xFFFF_FFFF;
+
+fn read_u32(file: &mut File) -> Option<u32> {
+    let mut bytes = [0u8; 4];
+    file.read_exact(&mut bytes).ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+fn read_id(file: &mut File) -> Option<[u8; 4]> {
+    let mut id = [0u8; 4];
+    file.read_exact(&mut id).ok()?;
+    Some(id)
+}
+
+/// contents of the iXML chunk of a RIFF/RF64 wave file
+pub fn ixml_chunk<T: AsRef<Path>>(path: T) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let riff = read_id(&mut file)?;
+    if &riff != b"RIFF" && &riff != b"RF64" {
+        return None
+    }
+    read_u32(&mut file)?;
+    if &read_id(&mut file)? != b"WAVE" {
+        return None
+    }
+    let mut data_size_64 = None;
+    loop {
+        let id = read_id(&mut file)?;
+        let size = read_u32(&mut file)?;
+        let size = match (size, data_size_64) {
+            (RF64_SIZE_PLACEHOLDER, Some(real)) if &id == b"data" => real,
+            (size, _) => size as u64,
+        };
+        match &id {
+            b"iXML" => {
+                let mut xml = vec![0u8; size as usize];
+                file.read_exact(&mut xml).ok()?;
+                return Some(String::from_utf8_lossy(&xml).into_owned())
+            },
+            b"ds64" => {
+                let mut ds64 = vec![0u8; size as usize];
+                file.read_exact(&mut ds64).ok()?;
+                // riff size (8 bytes) comes first, then the data size
+                let mut data_size = [0u8; 8];
+                data_size.copy_from_slice(ds64.get(8..16)?);
+                data_size_64 = Some(u64::from_le_bytes(data_size));
+                file.seek(SeekFrom::Current((size % 2) as i64)).ok()?;
+            },
+            _ => {
+                // chunks are padded to an even size
+                file.seek(SeekFrom::Current((size + size % 2) as i64)).ok()?;
+            },
+        }
+    }
+}
+
+fn tag_value<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = xml.find(&open)? + open.len();
+    let end = start + xml[start..].find(&close)?;
+    Some(xml[start..end].trim())
+}
+
+/// channel number (starting at 1) to the track name the recorder stored, eg. "Boom" or "Lav Anna"
+pub fn track_names(xml: &str) -> HashMap<u16, String> {
+    xml.split("<TRACK>")
+        .skip(1)
+        .filter_map(|track| {
+            let track = &track[..track.find("</TRACK>")?];
+            let index = tag_value(track, "CHANNEL_INDEX")?.parse().ok()?;
+            let name = tag_value(track, "NAME")?;
+            match name.is_empty() {
+                true => None,
+                false => Some((index, String::from(name))),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,83 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde_json::Value;
+
+/// EBU R128 measurement of a file or a mix
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// integrated loudness in LUFS
+    pub integrated: f64,
+    /// true peak in dBTP
+    pub true_peak: f64,
+    /// loudness range in LU
+    pub range: f64,
+    /// gating threshold in LUFS, needed for the second loudnorm pass
+    pub threshold: f64,
+}
+
+impl Loudness {
+    /// reads the measurement from ffmpeg's stderr after a `loudnorm=print_format=json` pass,
+    /// which ends with a json object holding every value as a string
+    pub fn from_loudnorm_output(output: &str) -> Option<Self> {
+        let start = output.rfind('{')?;
+        let json = &output[start..=start + output[start..].find('}')?];
+        let report: Value = serde_json::from_str(json).ok()?;
+        let number = |key: &str| report.get(key)?.as_str()?.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+        Some(Self {
+            integrated: number("input_i")?,
+            true_peak: number("input_tp")?,
+            range: number("input_lra")?,
+            threshold: number("input_thresh")?,
+        })
+    }
+
+    /// loudness of files played one after another, weighted by their duration.
+    /// ignores the gating across files, close enough for the parts of one recording
+    pub fn combine(parts: &[(Loudness, f64)]) -> Option<Self> {
+        let total: f64 = parts.iter().map(|(_, duration)| duration).sum();
+        if parts.is_empty() || total <= 0. {
+            return None
+        }
+        let energy = |value: f64| 10f64.powf(value / 10.);
+        let mean = |value: fn(&Loudness) -> f64| {
+            10. * (parts.iter().map(|(l, d)| energy(value(l)) * d).sum::<f64>() / total).log10()
+        };
+        Some(Self {
+            integrated: mean(|l| l.integrated),
+            true_peak: parts.iter().map(|(l, _)| l.true_peak).fold(f64::MIN, f64::max),
+            range: parts.iter().map(|(l, _)| l.range).fold(f64::MIN, f64::max),
+            threshold: mean(|l| l.threshold),
+        })
+    }
+
+    /// gain in dB bringing the integrated loudness to `target`
+    pub fn gain_to(&self, target: f64) -> f64 {
+        target - self.integrated
+    }
+
+    /// second pass of a two-pass normalization to `target` LUFS, using this measurement
+    pub fn loudnorm_filter(&self, target: f64) -> String {
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:linear=true",
+            target,
+            TRUE_PEAK_CEILING,
+            self.range.clamp(1., 20.).ceil(),
+            self.integrated,
+            self.true_peak,
+            self.range,
+            self.threshold,
+        )
+    }
+}
+
+/// highest true peak normalization may produce, in dBTP
+pub const TRUE_PEAK_CEILING: f64 = -1.;
+
+/// filter measuring loudness, ffmpeg prints the result to stderr when done
+pub const LOUDNORM_ANALYSIS: &str = "loudnorm=print_format=json";
+
+impl Display for Loudness {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} LUFS, {:.1} dBTP, LRA {:.1} LU", self.integrated, self.true_peak, self.range)
+    }
+}
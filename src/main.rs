@@ -1,43 +1,47 @@
 mod media_file;
+mod clip;
 mod ffmpeg_wrapper;
 mod devices;
 mod select;
 mod daw_support;
+mod hashing;
+mod ixml;
+mod sync;
+mod mix;
+mod render;
+mod loudness;
+mod proxy;
+mod ingest;
+mod dedup;
+mod manifest;
+mod overrides;
+mod report;
+mod timeline;
+mod review;
+mod terminal;
+mod watch;
+mod walk;
+mod dates;
+mod output;
+mod cli;
+mod commands;
 pub mod daws;
 
 mod tests;
-use daw_support::DAWProjectFile;
-use std::path::PathBuf;
 use std::error::Error;
 use structopt::StructOpt;
-use crate::media_file::{media_files};
-use crate::ffmpeg_wrapper::Ffmpeg;
-use crate::select::Select;
-use crate::daws::reaper::{ReaperTrack, Reaper};
-
-#[derive(StructOpt, Debug)]
-#[structopt(name = "smergy")]
-struct Cli {
-    #[structopt(short, long, parse(from_os_str))]
-    pub directories: Vec<PathBuf>,
-}
+use crate::cli::Cli;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let cli: Cli = Cli::from_args();
-    println!("FFMPEG version found: {}\n", Ffmpeg::version().unwrap());
-    for (video, audios) in Select::candidates(&cli.directories) {
-        let video = video.make_copy().unwrap();
-        let audios: Vec<_> = audios.into_iter().map(|a| a.make_copy().unwrap()).collect();
-        println!("\n# {}", video);
-        let command = String::from("reaper");
-        println!("{} \"{}\"", command, video.cli_friendly_path());
-//        for audio in audios {
-//            println!("{} \"{}\"", command, audio.cli_friendly_path());
-//        }
-
-        let tracks: Vec<ReaperTrack> = vec![video].into_iter().chain(audios.into_iter()).map(|i| i.into()).collect();
-        let project_file = Reaper::new(tracks, String::from("1578133999"));
-        project_file.save();
+    match Cli::from_args() {
+        Cli::Scan(args) => commands::scan(&args),
+        Cli::Plan(args) => commands::plan(&args),
+        Cli::Sync(args) => commands::sync(&args),
+        Cli::Review(args) => commands::review(&args),
+        Cli::Export(args) => commands::export(&args),
+        Cli::Render(args) => commands::render(&args),
+        Cli::Watch(args) => commands::watch(&args),
+        Cli::Inspect(args) => commands::inspect(&args),
+        Cli::Relink(args) => commands::relink(&args),
     }
-    Ok(())
 }
@@ -0,0 +1,244 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDateTime, Duration};
+use serde::{Serialize, Deserialize};
+
+use crate::clip::Clip;
+use crate::ingest::Session;
+use crate::media_file::{MediaFile, media_files};
+use crate::select::VideoAudioGroup;
+
+/// bumped whenever a change to the format would make older smergy versions misread a manifest
+pub const MANIFEST_VERSION: u32 = 2;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.6f";
+
+/// everything scanning, grouping and syncing found out, saved so projects can be made again without rescanning.
+/// meant to be reviewed and corrected by hand
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub version: u32,
+    pub groups: Vec<ManifestGroup>,
+}
+
+/// a video and the recordings made alongside it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestGroup {
+    pub session: String,
+    /// when the video starts, the recordings are placed relative to it
+    pub start: Option<String>,
+    pub video: ManifestClip,
+    pub audio: Vec<ManifestClip>,
+    /// decided in review, rejected groups are left out of projects and renders. none until reviewed
+    #[serde(default)]
+    pub approved: Option<bool>,
+}
+
+/// one recording, possibly split into several files
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestClip {
+    pub files: Vec<ManifestFile>,
+    pub device: Option<String>,
+    /// where the timing comes from, eg. `zoom-h6 metadata`, `filesystem` or `manual`
+    pub timing_source: String,
+    /// seconds from the start of the video, negative when the recording started first
+    pub offset: Option<f64>,
+    /// parts per million the device's clock runs faster than the video's
+    #[serde(default)]
+    pub drift_ppm: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ManifestFile {
+    pub path: String,
+    pub hash: Option<String>,
+    pub duration: Option<f64>,
+    /// other places the same file was found
+    #[serde(default)]
+    pub duplicates: Vec<String>,
+}
+
+impl Manifest {
+    pub fn from_groups(groups: &[VideoAudioGroup]) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            groups: groups.iter().map(|(video, audios)| ManifestGroup::from_group(video, audios)).collect(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, ManifestError> {
+        let json = fs::read_to_string(path).map_err(|e| ManifestError::new(path, &e.to_string()))?;
+        let mut manifest: Self = serde_json::from_str(&json).map_err(|e| ManifestError::new(path, &e.to_string()))?;
+        if manifest.version > MANIFEST_VERSION {
+            return Err(ManifestError::new(path, &format!(
+                "written by a newer smergy (version {}, this one reads up to {})",
+                manifest.version,
+                MANIFEST_VERSION,
+            )))
+        }
+        // paths written by hand relative to the manifest work wherever it's used from
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        for group in manifest.groups.iter_mut() {
+            for file in std::iter::once(&mut group.video).chain(group.audio.iter_mut()).flat_map(|c| c.files.iter_mut()) {
+                file.path = resolved(directory, &file.path);
+                file.duplicates = file.duplicates.iter().map(|d| resolved(directory, d)).collect();
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ManifestError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| ManifestError::new(path, &e.to_string()))?;
+        fs::write(path, json + "\n").map_err(|e| ManifestError::new(path, &e.to_string()))
+    }
+
+    /// the manifest without the groups rejected in review
+    pub fn without_rejected(mut self) -> Self {
+        self.groups.retain(|g| g.approved != Some(false));
+        self
+    }
+
+    /// the groups, with the files probed again but timed as the manifest says
+    pub fn groups(&self) -> Result<Vec<VideoAudioGroup>, ManifestError> {
+        self.groups.iter().map(|g| g.to_group()).collect()
+    }
+}
+
+impl ManifestGroup {
+    fn from_group(video: &Clip, audios: &[Clip]) -> Self {
+        let start = video.start();
+        Self {
+            session: Session::of(video).name,
+            start: start.map(|s| s.format(TIME_FORMAT).to_string()),
+            video: ManifestClip::from_clip(video, start),
+            audio: audios.iter().map(|a| ManifestClip::from_clip(a, start)).collect(),
+            approved: None,
+        }
+    }
+
+    fn to_group(&self) -> Result<VideoAudioGroup, ManifestError> {
+        let start = match &self.start {
+            Some(start) => Some(
+                NaiveDateTime::parse_from_str(start, TIME_FORMAT)
+                    .map_err(|e| ManifestError::in_manifest(&format!("session {} has a bad start {}: {}", self.session, start, e)))?
+            ),
+            None => None,
+        };
+        let video = self.video.to_clip(start)?;
+        let audios = self.audio.iter().map(|a| a.to_clip(start)).collect::<Result<_, _>>()?;
+        Ok((video, audios))
+    }
+}
+
+impl ManifestClip {
+    pub(crate) fn from_clip(clip: &Clip, video_start: Option<NaiveDateTime>) -> Self {
+        let offset = match (clip.start(), video_start) {
+            (Some(start), Some(video_start)) => (start - video_start).num_microseconds().map(|us| us as f64 / 1_000_000.),
+            _ => None,
+        };
+        Self {
+            files: clip.parts.iter().map(ManifestFile::from_media_file).collect(),
+            device: clip.device().map(String::from),
+            timing_source: clip.timing_source(),
+            offset,
+            drift_ppm: clip.drift_ppm,
+        }
+    }
+
+    fn to_clip(&self, video_start: Option<NaiveDateTime>) -> Result<Clip, ManifestError> {
+        let parts = self.files.iter().map(|f| f.to_media_file()).collect::<Result<Vec<_>, _>>()?;
+        if parts.is_empty() {
+            return Err(ManifestError::in_manifest("a clip without files"))
+        }
+        Ok(self.timed(parts, video_start))
+    }
+
+    /// the clip made of `parts` (probed again) timed as the manifest says
+    pub(crate) fn timed(&self, parts: Vec<MediaFile>, video_start: Option<NaiveDateTime>) -> Clip {
+        let clip = Clip::new(parts).with_drift(self.drift_ppm);
+        let clip = match &self.device {
+            Some(device) => clip.with_device(device),
+            None => clip,
+        };
+        let start = match (video_start, self.offset) {
+            (Some(video_start), Some(offset)) => video_start + Duration::microseconds((offset * 1_000_000.).round() as i64),
+            _ => return clip,
+        };
+        // only starts corrected by hand override the metadata
+        let corrected = match clip.start() {
+            Some(metadata) => (metadata - start).num_milliseconds().abs() >= 1,
+            None => true,
+        };
+        match corrected {
+            true => clip.with_start(start),
+            false => clip,
+        }
+    }
+}
+
+impl ManifestFile {
+    /// the paths are saved absolute, the manifest is replayed from other directories than the one scanned from
+    pub(crate) fn from_media_file(file: &MediaFile) -> Self {
+        let absolute = |path: &Path| fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).display().to_string();
+        Self {
+            path: absolute(file.entry.path()),
+            hash: file.content_hash(),
+            duration: file.exact_duration(),
+            duplicates: file.duplicates().iter().map(|d| absolute(d)).collect(),
+        }
+    }
+
+    /// probes the file again, falling back to its duplicates when it moved or was replaced by other content
+    fn to_media_file(&self) -> Result<MediaFile, ManifestError> {
+        let mut file = std::iter::once(&self.path)
+            .chain(self.duplicates.iter())
+            .find_map(|path| media_files(&PathBuf::from(path)).into_iter().find(|f| self.is(f)))
+            .ok_or_else(|| ManifestError::new(Path::new(&self.path), "missing, not readable or changed media"))?;
+        let found_at = String::from(file.full_path());
+        for duplicate in self.duplicates.iter().filter(|d| **d != found_at) {
+            file.add_duplicate(PathBuf::from(duplicate));
+        }
+        Ok(file)
+    }
+
+    /// whether `file` holds the recording the manifest was saved with, any file does when no hash was recorded
+    pub(crate) fn is(&self, file: &MediaFile) -> bool {
+        self.hash.is_none() || file.content_hash() == self.hash
+    }
+}
+
+/// `path` taken from `directory` when it's relative
+fn resolved(directory: &Path, path: &str) -> String {
+    directory.join(path).display().to_string()
+}
+
+#[derive(Debug)]
+pub struct ManifestError {
+    /// the manifest or the media file the problem is with, none for a problem inside the manifest
+    path: Option<PathBuf>,
+    message: String,
+}
+
+impl ManifestError {
+    fn new(path: &Path, message: &str) -> Self {
+        Self { path: Some(path.to_path_buf()), message: String::from(message) }
+    }
+
+    fn in_manifest(message: &str) -> Self {
+        Self { path: None, message: String::from(message) }
+    }
+}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "manifest {}: {}", path.display(), self.message),
+            None => write!(f, "manifest: {}", self.message),
+        }
+    }
+}
+
+impl Error for ManifestError {}
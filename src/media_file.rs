@@ -1,17 +1,21 @@
+use std::cell::OnceCell;
 use std::path::{Path, PathBuf};
 use std::iter::Iterator;
 use std::fmt::Write;
 
-use walkdir::{WalkDir, DirEntry};
+use walkdir::DirEntry;
 use chrono::{
     NaiveDateTime,
     Duration,
 };
 
-use std::fs::{copy, create_dir};
-
-use crate::ffmpeg_wrapper::{Ffmpeg, FfmpegFileData};
-use crate::devices::{DeviceDatetimeGetter, device_metadata_parsers};
+use crate::ffmpeg_wrapper::{Ffmpeg, FfmpegFileData, RawStream};
+use crate::hashing::quick_hash;
+use crate::loudness::Loudness;
+use crate::ixml::{ixml_chunk, track_names};
+use crate::walk::WalkFilter;
+use std::collections::HashMap;
+use crate::devices::{device_metadata_parsers, devices};
 use serde::export::fmt::{Display, Error};
 use serde::export::Formatter;
 
@@ -54,11 +58,60 @@ pub fn duration_pretty(duration: Duration) -> Option<String> {
     Some(out)
 }
 
+/// parses ffprobe ratios such as "30000/1001", skipping the "0/0" ffprobe reports for unknown values
+pub fn parse_ratio(ratio: &str) -> Option<(u32, u32)> {
+    let mut parts = ratio.trim().split('/');
+    let num = parts.next()?.parse::<u32>().ok()?;
+    let den = match parts.next() {
+        Some(den) => den.parse::<u32>().ok()?,
+        None => 1,
+    };
+    if num == 0 || den == 0 { return None }
+    Some((num, den))
+}
+
+/// seconds since midnight of a `HH:MM:SS:FF` timecode (`;` before the frames for drop frame) at `frame_rate`
+pub fn parse_timecode(timecode: &str, frame_rate: (u32, u32)) -> Option<f64> {
+    let fields: Vec<f64> = timecode.split([':', ';', '.']).map(|f| f.trim().parse::<f64>().ok()).collect::<Option<_>>()?;
+    match fields.as_slice() {
+        [hours, minutes, seconds, frames] if frame_rate.0 > 0 => {
+            Some(hours * 3600. + minutes * 60. + seconds + frames * frame_rate.1 as f64 / frame_rate.0 as f64)
+        },
+        _ => None,
+    }
+}
+
+/// file name without its take numbers, dates and times, files from the same input of a recorder share it
+/// (ZOOM0001_LR.WAV, ZOOM0002_LR.WAV). short numbers are kept, they tell the tracks apart (ZOOM0001_Tr1.WAV, ZOOM0001_Tr2.WAV)
+pub fn name_family(file_name: &str) -> String {
+    let mut family = String::new();
+    let mut digits = String::new();
+    for c in file_name.chars().chain(std::iter::once(' ')) {
+        match c.is_ascii_digit() {
+            true => digits.push(c),
+            false => {
+                if digits.len() < 3 {
+                    family.push_str(&digits);
+                }
+                digits.clear();
+                family.push(c);
+            },
+        }
+    }
+    family.pop();
+    family.to_lowercase()
+}
+
 pub fn media_files<T: AsRef<Path>>(directory: &T) -> Vec<MediaFile> {
-    WalkDir::new(directory)
+    walked_media_files(directory, &WalkFilter::default())
+}
+
+/// media files in `directory` that `filter` lets through
+pub fn walked_media_files<T: AsRef<Path>>(directory: &T, filter: &WalkFilter) -> Vec<MediaFile> {
+    filter
+        .entries(directory.as_ref())
         .into_iter()
-        .filter_map(|e| e.ok())
-        .filter_map(|e| MediaFile::from_entry(e))
+        .filter_map(MediaFile::from_entry)
         .collect()
 }
 
@@ -66,35 +119,61 @@ pub fn media_files<T: AsRef<Path>>(directory: &T) -> Vec<MediaFile> {
 pub struct MediaFile {
     pub entry: DirEntry,
     ffmpeg_metadata: Option<FfmpegFileData>,
-}
-
-pub fn get_tmp_entry(name: String) -> Option<PathBuf> {
-    let path = PathBuf::from(format!("/tmp/{}/", name));
-    create_dir(&path);
-    if !path.exists() { return None }
-    Some(path)
+    /// where identical copies of this file were found, eg. on backup drives
+    duplicates: Vec<PathBuf>,
+    /// read once, grouping and overrides ask for it over and over
+    content_hash: OnceCell<Option<String>>,
 }
 
 impl MediaFile {
     pub fn from_entry(entry: DirEntry) -> Option<Self> {
-        let mut media_file = MediaFile { entry, ffmpeg_metadata: None };
+        let media_file = MediaFile { entry, ffmpeg_metadata: None, duplicates: vec![], content_hash: OnceCell::new() };
         if !media_file.pre_validate() {
             return None
         }
 
-        media_file.ffmpeg_metadata = Ffmpeg::media_file_metadata_raw(media_file.entry.path());
+        let ffmpeg_metadata = Ffmpeg::media_file_metadata_raw(media_file.entry.path())?;
+        Self::from_probe(media_file.entry, ffmpeg_metadata)
+    }
 
-        if !media_file.validate() {
+    /// a file with metadata probed beforehand, eg. ffprobe's json kept from an earlier run
+    pub fn from_probe(entry: DirEntry, ffmpeg_metadata: FfmpegFileData) -> Option<Self> {
+        let media_file = MediaFile { entry, ffmpeg_metadata: Some(ffmpeg_metadata), duplicates: vec![], content_hash: OnceCell::new() };
+        if !media_file.pre_validate() || !media_file.validate() {
             return None
         }
 
         Some(media_file)
     }
 
+    /// remembers another location holding the same content
+    pub fn add_duplicate(&mut self, path: PathBuf) {
+        self.duplicates.push(path);
+    }
+
+    /// forgets the other locations `keep` says no to, eg. ones deleted since
+    pub fn retain_duplicates(&mut self, keep: impl Fn(&Path) -> bool) {
+        self.duplicates.retain(|p| keep(p));
+    }
+
+    pub fn duplicates(&self) -> &[PathBuf] {
+        &self.duplicates
+    }
+
+    /// every place this recording was found, the one in use first
+    pub fn locations(&self) -> Vec<&Path> {
+        std::iter::once(self.entry.path()).chain(self.duplicates.iter().map(|p| p.as_path())).collect()
+    }
+
     pub fn ffmpeg_data_raw(&self) -> Option<&FfmpegFileData> {
         self.ffmpeg_metadata.as_ref()
     }
 
+    /// quick content hash, stays the same when the file is copied or renamed
+    pub fn content_hash(&self) -> Option<String> {
+        self.content_hash.get_or_init(|| quick_hash(self.entry.path())).clone()
+    }
+
     pub fn extension(&self) -> Option<String> {
         Some(self.entry.path().extension()?.to_str()?.to_lowercase())
     }
@@ -187,6 +266,28 @@ impl MediaFile {
         Some(Duration::microseconds(self.duration_raw_microseconds()?))
     }
 
+    /// duration in seconds, counted in samples for audio so that consecutive files can be placed back to back exactly
+    pub fn exact_duration(&self) -> Option<f64> {
+        let samples = self.audio_streams().find_map(|s| {
+            let rate = s.sample_rate.as_ref()?.parse::<u32>().ok()?;
+            // duration_ts is only a sample count when the time base is one sample
+            match s.time_base.as_deref() == Some(format!("1/{}", rate).as_str()) {
+                true => Some(s.duration_ts? as f64 / rate as f64),
+                false => None,
+            }
+        });
+        match (self.media_type(), samples) {
+            (Some(MediaType::Audio), Some(seconds)) => Some(seconds),
+            _ => Some(self.duration_raw_microseconds()? as f64 / 1_000_000.),
+        }
+    }
+
+    /// EBU R128 measurement of the file's audio, runs a full decode so it's only done on request
+    pub fn loudness(&self) -> Option<Loudness> {
+        self.audio_streams().next()?;
+        Ffmpeg::loudness(self.entry.path())
+    }
+
     pub fn duration_pretty(&self) -> Option<String> {
         duration_pretty(self.duration()?)
     }
@@ -198,10 +299,83 @@ impl MediaFile {
             .next()
     }
 
+    /// name of the first device whose metadata this file carries
+    pub fn device(&self) -> Option<&'static str> {
+        devices()
+            .into_iter()
+            .find(|d| (d.creation_time)(self).is_some())
+            .map(|d| d.name)
+    }
+
     pub fn start(&self) -> Option<NaiveDateTime> {
         Some(self.end()? - self.duration()?)
     }
 
+    /// time elapsed between `origin` and the start of this file
+    pub fn offset_from(&self, origin: NaiveDateTime) -> Option<Duration> {
+        Some(self.start()? - origin)
+    }
+
+    fn stream_of_type(&self, codec_type: &str) -> Option<&RawStream> {
+        self.ffmpeg_data_raw()?
+            .streams
+            .iter()
+            .find(|s| s.codec_type.as_deref() == Some(codec_type))
+    }
+
+    fn audio_streams(&self) -> impl Iterator<Item = &RawStream> {
+        self.ffmpeg_data_raw()
+            .into_iter()
+            .flat_map(|d| d.streams.iter())
+            .filter(|s| s.codec_type.as_deref() == Some("audio"))
+    }
+
+    /// highest sample rate among the audio streams
+    pub fn sample_rate(&self) -> Option<u32> {
+        self.audio_streams()
+            .filter_map(|s| s.sample_rate.as_ref()?.parse::<u32>().ok())
+            .max()
+    }
+
+    /// channel count of the widest audio stream
+    pub fn channels(&self) -> Option<u16> {
+        self.audio_streams().filter_map(|s| s.channels).max()
+    }
+
+    /// channel names stored in the iXML chunk of wave files, by channel number
+    pub fn channel_names(&self) -> HashMap<u16, String> {
+        match self.media_type() {
+            Some(MediaType::Audio) => ixml_chunk(self.entry.path()).map(|x| track_names(&x)).unwrap_or_default(),
+            _ => HashMap::new(),
+        }
+    }
+
+    pub fn video_stream(&self) -> Option<&RawStream> {
+        self.stream_of_type("video")
+    }
+
+    /// (width, height) of the first video stream
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        let stream = self.video_stream()?;
+        Some((stream.width?, stream.height?))
+    }
+
+    /// (numerator, denominator) of the first video stream's `r_frame_rate`, eg. (30000, 1001)
+    pub fn frame_rate(&self) -> Option<(u32, u32)> {
+        parse_ratio(self.video_stream()?.r_frame_rate.as_ref()?)
+    }
+
+    /// timecode of the first frame or sample in seconds since midnight, from a video's timecode or a wave file's BWF time reference
+    pub fn timecode(&self) -> Option<f64> {
+        let data = self.ffmpeg_data_raw()?;
+        let tags = data.format.as_ref().and_then(|f| f.tags.as_ref());
+        if let Some(reference) = tags.and_then(|t| t.time_reference.as_ref()) {
+            return Some(reference.parse::<u64>().ok()? as f64 / self.sample_rate()? as f64)
+        }
+        let timecode = data.streams.iter().find_map(|s| s.tags.get("timecode")).or(tags.and_then(|t| t.timecode.as_ref()))?;
+        parse_timecode(timecode, self.frame_rate()?)
+    }
+
     pub fn start_pretty(&self) -> Option<String> {
         Some(self.start()?.format("%Y-%m-%d %H:%M:%S").to_string())
     }
@@ -217,21 +391,6 @@ impl MediaFile {
     pub fn cli_friendly_path(&self) -> String {
         self.full_path().clone().replace(" ", "\\ ")
     }
-
-    pub fn tmp_entry(&self) -> Option<PathBuf> {
-        let mut entry = get_tmp_entry(self.base_name()?)?;
-        entry.push(PathBuf::from(self.filename()));
-        Some(entry)
-    }
-
-    pub fn make_copy(&self) -> Option<Self> {
-        let copy_path = self.tmp_entry()?;
-        if !copy_path.exists() {
-            println!("# copying {}...", self.filename());
-            copy(self.entry.path(), copy_path).ok()?;
-        }
-        media_files(&self.tmp_entry()?.parent()?).into_iter().next()
-    }
 }
 
 impl Display for MediaFile {
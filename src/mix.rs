@@ -0,0 +1,183 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::daw_support::ChannelMapping;
+use crate::devices::devices;
+
+/// mix settings of device profiles kept between runs, below the user's config directory
+pub const MIX_PROFILES_FILE: &str = "smergy/mix-profiles";
+
+/// how a single source goes into the mixdown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MixSettings {
+    pub gain_db: f64,
+    /// -1 is hard left, 1 hard right
+    pub pan: f64,
+    pub channels: ChannelMapping,
+    pub mute: bool,
+}
+
+impl Default for MixSettings {
+    fn default() -> Self {
+        Self { gain_db: 0., pan: 0., channels: ChannelMapping::All, mute: false }
+    }
+}
+
+impl FromStr for MixSettings {
+    type Err = String;
+    /// comma separated settings, eg. `gain=-3,pan=-0.5,channels=3-4` or `mute`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = Self::default();
+        for setting in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let number = |v: &str| v.trim().parse::<f64>().map_err(|_| format!("not a number: {}", v));
+            match setting.split_once('=') {
+                Some(("gain", value)) => settings.gain_db = number(value)?,
+                Some(("pan", value)) => settings.pan = number(value)?.clamp(-1., 1.),
+                Some(("channels", value)) => settings.channels = value.parse()?,
+                None if setting == "mute" => settings.mute = true,
+                _ => return Err(format!("unknown mix setting: {}", setting)),
+            }
+        }
+        Ok(settings)
+    }
+}
+
+/// mix settings given for a run, applying to a device or to a single file (`zoom-h6:gain=-3`, `ZOOM0001_TR1.WAV:mute`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct MixRule {
+    pub target: String,
+    pub settings: MixSettings,
+}
+
+impl FromStr for MixRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (target, settings) = s.split_once(':').ok_or_else(|| format!("expected <device or file>:<settings>, got {}", s))?;
+        Ok(Self { target: String::from(target.trim()), settings: settings.parse()? })
+    }
+}
+
+/// mix settings for device profiles, a `device:settings` rule per line (`zoom-h6:gain=-3,pan=-0.2`), `#` starting a comment.
+/// they go before the rules of a run, which win over them
+pub fn load_mix_profiles(path: &Path) -> Result<Vec<MixRule>, String> {
+    let profiles = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    profiles
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let rule: MixRule = line.parse().map_err(|e| format!("{}:{}: {}", path.display(), number, e))?;
+            match devices().iter().any(|d| d.name == rule.target) {
+                true => Ok(rule),
+                false => Err(format!("{}:{}: there's no device profile called {}", path.display(), number, rule.target)),
+            }
+        })
+        .collect()
+}
+
+/// the mix profiles file in the config directory, when there is one
+pub fn default_mix_profiles() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join(MIX_PROFILES_FILE)).filter(|path| path.exists())
+}
+
+/// settings for a clip: a rule naming its file wins over one naming its device, which wins over the device profile
+pub fn settings_for(rules: &[MixRule], file_name: &str, device: &str, profile: MixSettings) -> MixSettings {
+    let matching = |name: &str| rules.iter().rev().find(|r| r.target.eq_ignore_ascii_case(name)).map(|r| r.settings);
+    matching(file_name).or_else(|| matching(device)).unwrap_or(profile)
+}
+
+/// one recording in the mixdown, its parts played back to back
+#[derive(Debug, Clone)]
+pub struct MixSource {
+    pub audio: Vec<PathBuf>,
+    /// seconds from the start of the video to the start of the recording, negative when the recording started first
+    pub offset: f64,
+    pub settings: MixSettings,
+    /// parts per million the recorder's clock runs faster than the video's
+    pub drift_ppm: f64,
+}
+
+impl MixSource {
+    pub fn new(audio: Vec<PathBuf>, offset: f64) -> Self {
+        Self { audio, offset, settings: MixSettings::default(), drift_ppm: 0. }
+    }
+
+    /// plays the recording faster or slower to stay in sync, like the reaper export does
+    pub fn with_drift(mut self, drift_ppm: f64) -> Self {
+        self.drift_ppm = drift_ppm;
+        self
+    }
+
+    pub fn with_settings(mut self, settings: MixSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// filter chain turning the inputs starting at `first_input` into the source's stereo contribution labelled `output`
+    fn chain(&self, first_input: usize, output: &str) -> String {
+        let inputs: String = (first_input..first_input + self.audio.len()).map(|i| format!("[{}:a]", i)).collect();
+        let mut filters = vec![format!("{}concat=n={}:v=0:a=1", inputs, self.audio.len())];
+        if self.drift_ppm != 0. {
+            filters.push(format!("atempo={:.9}", 1. + self.drift_ppm / 1_000_000.));
+        }
+        let (left, right) = balance(self.settings.pan);
+        match (self.settings.channels, self.settings.pan == 0.) {
+            (ChannelMapping::All, true) => {},
+            (ChannelMapping::All, false) => {
+                filters.push(String::from("aformat=channel_layouts=stereo"));
+                filters.push(format!("pan=stereo|c0={}*c0|c1={}*c1", left, right));
+            },
+            (ChannelMapping::Mono(c), _) => filters.push(format!("pan=stereo|c0={}*c{}|c1={}*c{}", left, c - 1, right, c - 1)),
+            (ChannelMapping::Pair(c), _) => filters.push(format!("pan=stereo|c0={}*c{}|c1={}*c{}", left, c - 1, right, c)),
+        }
+        match self.offset {
+            o if o > 0. => filters.push(format!("adelay={}:all=1", (o * 1000.).round() as i64)),
+            o if o < 0. => filters.push(format!("atrim=start={:.6},asetpts=PTS-STARTPTS", -o)),
+            _ => {},
+        }
+        if self.settings.gain_db != 0. {
+            filters.push(format!("volume={}dB", self.settings.gain_db));
+        }
+        format!("{}[{}]", filters.join(","), output)
+    }
+}
+
+/// left and right gains for a balance position, the centre leaves both untouched
+fn balance(pan: f64) -> (f64, f64) {
+    ((1. - pan).min(1.), (1. + pan).min(1.))
+}
+
+/// filter_complex mixing the audible `sources` into `[external]`, lasting exactly `span` seconds.
+/// ffmpeg inputs are numbered from `first_input` in the order of the sources and their parts
+pub fn mix_filter(sources: &[MixSource], first_input: usize, span: f64) -> String {
+    let mut chains = vec![];
+    let mut labels = vec![];
+    let mut input = first_input;
+    for source in sources {
+        if !source.settings.mute {
+            let label = format!("s{}", labels.len());
+            chains.push(source.chain(input, &label));
+            labels.push(format!("[{}]", label));
+        }
+        input += source.audio.len();
+    }
+    let ending = format!("apad,atrim=end={:.6}[external]", span);
+    match labels.len() {
+        0 => format!("anullsrc=r=48000:cl=stereo,atrim=end={:.6}[external]", span),
+        1 => format!("{},{}", chains[0].trim_end_matches("[s0]"), ending),
+        // amix divides every input by their count, which keeps the sum from clipping. --normalize brings the level back up
+        n => format!(
+            "{};{}amix=inputs={}:duration=longest,{}",
+            chains.join(";"),
+            labels.concat(),
+            n,
+            ending,
+        ),
+    }
+}
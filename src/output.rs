@@ -0,0 +1,166 @@
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::clip::Clip;
+use crate::ingest::Session;
+
+/// what projects are named after, `{date}`, `{time}`, `{session}`, `{device}` and `{first_file}` are filled in per group
+pub const DEFAULT_NAME: &str = "{session}";
+
+/// what to do when a project file by the name already exists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Collision {
+    /// `-2`, `-3`... is appended to the name until it's free
+    Suffix,
+    Overwrite,
+    Skip,
+}
+
+/// the values a name template is filled in with
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectName {
+    pub date: String,
+    pub time: String,
+    pub session: String,
+    pub device: String,
+    /// the file that starts recording first, video or audio
+    pub first_file: String,
+}
+
+impl ProjectName {
+    pub fn of(video: &Clip, audios: &[Clip]) -> Self {
+        let session = Session::of(video);
+        let first = std::iter::once(video)
+            .chain(audios.iter())
+            .filter(|c| c.start().is_some())
+            .min_by_key(|c| c.start())
+            .unwrap_or(video)
+            .first();
+        Self {
+            date: session.date,
+            time: video.start().map(|s| s.format("%H%M%S").to_string()).unwrap_or_else(|| String::from("untimed")),
+            session: session.name,
+            device: String::from(video.device().unwrap_or("unknown")),
+            first_file: first.base_name().map(|b| String::from(b.trim_end_matches('.'))).unwrap_or_else(|| first.filename()),
+        }
+    }
+
+    /// fills in `template`, keeping every value a single path component. `/` in the template itself makes directories
+    pub fn render(&self, template: &str) -> PathBuf {
+        let component = |value: &str| value.replace(['/', '\\'], "_");
+        PathBuf::from(
+            template
+                .replace("{date}", &component(&self.date))
+                .replace("{time}", &component(&self.time))
+                .replace("{session}", &component(&self.session))
+                .replace("{device}", &component(&self.device))
+                .replace("{first_file}", &component(&self.first_file))
+        )
+    }
+}
+
+/// where exported projects go and how they're named
+#[derive(Debug, Clone)]
+pub struct ProjectOutput {
+    directory: PathBuf,
+    name: String,
+    collision: Collision,
+    relative_media: bool,
+}
+
+impl ProjectOutput {
+    /// projects named after `name` in `directory`
+    pub fn new(directory: PathBuf, name: &str) -> Self {
+        Self { directory, name: String::from(name), collision: Collision::Suffix, relative_media: true }
+    }
+
+    pub fn with_collision(mut self, collision: Collision) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    /// media paths in the projects are written relative to the project, where they can be
+    pub fn with_relative_media(mut self, relative_media: bool) -> Self {
+        self.relative_media = relative_media;
+        self
+    }
+
+    pub fn relative_media(&self) -> bool {
+        self.relative_media
+    }
+
+    /// path to save the project named by `name` to, `None` when it exists and is to be skipped
+    pub fn path_for(&self, name: &ProjectName, extension: &str) -> Option<PathBuf> {
+        let stem = self.directory.join(name.render(&self.name));
+        let candidate = |attempt: usize| match attempt {
+            1 => PathBuf::from(format!("{}.{}", stem.display(), extension)),
+            n => PathBuf::from(format!("{}-{}.{}", stem.display(), n, extension)),
+        };
+        match self.collision {
+            Collision::Overwrite => Some(candidate(1)),
+            Collision::Skip => Some(candidate(1)).filter(|path| !path.exists()),
+            Collision::Suffix => (1..).map(candidate).find(|path| !path.exists()),
+        }
+    }
+}
+
+/// `path` as seen from `directory`, when both are absolute and share a directory below the root.
+/// media far away from the project, eg. on another card or drive, keeps its absolute path
+pub fn relative_path(path: &Path, directory: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() || !directory.is_absolute() {
+        return None
+    }
+    let path: Vec<Component> = path.components().collect();
+    let directory: Vec<Component> = directory.components().collect();
+    let common = path.iter().zip(&directory).take_while(|(one, other)| one == other).count();
+    let roots = path.iter().take_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir)).count();
+    if common <= roots {
+        return None
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..directory.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component.as_os_str());
+    }
+    Some(relative)
+}
+
+/// where the previous version of the file at `path` is kept, `-bak` is added to its extension
+pub fn backup_path(path: &Path) -> PathBuf {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("rpp");
+    path.with_extension(format!("{}-bak", extension))
+}
+
+/// copies the file at `path` to its backup before it's replaced, `None` when there's nothing there yet
+pub fn keep_backup(path: &Path) -> io::Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None)
+    }
+    let backup = backup_path(path);
+    fs::copy(path, &backup)?;
+    Ok(Some(backup))
+}
+
+/// `path` resolved to an absolute one, as is when it can't be
+pub fn absolute_path(path: &str) -> String {
+    match fs::canonicalize(path) {
+        Ok(absolute) => absolute.display().to_string(),
+        Err(_) => String::from(path),
+    }
+}
+
+/// `path` written relative to the project saved in `directory`, as is when it can't be
+pub fn media_path(path: &str, directory: &Path) -> String {
+    let absolute = fs::canonicalize(path).ok();
+    let directory = fs::canonicalize(directory).ok();
+    match (absolute, directory) {
+        (Some(absolute), Some(directory)) => match relative_path(&absolute, &directory) {
+            Some(relative) => relative.display().to_string(),
+            None => absolute.display().to_string(),
+        },
+        _ => String::from(path),
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDateTime, Duration};
+use serde::{Serialize, Deserialize};
+
+use crate::clip::Clip;
+use crate::devices::devices;
+use crate::media_file::MediaFile;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+
+/// corrections made by hand for where metadata is wrong, eg. a phone whose clock was reset.
+/// files are keyed by their content hash (as `inspect` shows it), so corrections survive renames, copies and rescans
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    #[serde(default)]
+    pub files: HashMap<String, FileOverride>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct FileOverride {
+    /// for whoever edits the file, eg. the name of the file or why it's corrected
+    #[serde(default)]
+    pub note: Option<String>,
+    /// leaves the file out entirely
+    #[serde(default)]
+    pub exclude: bool,
+    /// when the recording really started, as `2020-01-05 14:32:10.5`
+    #[serde(default)]
+    pub start: Option<String>,
+    /// start relative to the start of another file
+    #[serde(default)]
+    pub offset: Option<RelativeOffset>,
+    /// content hash of the video the recording goes with, and only with
+    #[serde(default)]
+    pub group: Option<String>,
+    /// content hashes of videos the recording doesn't go with even when it overlaps them
+    #[serde(default)]
+    pub not_in: Vec<String>,
+    /// device profile to use for the recording
+    #[serde(default)]
+    pub device: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RelativeOffset {
+    /// content hash of the other file
+    pub from: String,
+    /// seconds after the other file started, negative when this one started first
+    pub seconds: f64,
+}
+
+impl Overrides {
+    pub fn load(path: &Path) -> Result<Self, OverridesError> {
+        let json = fs::read_to_string(path).map_err(|e| OverridesError::new(path, &e.to_string()))?;
+        let overrides: Self = serde_json::from_str(&json).map_err(|e| OverridesError::new(path, &e.to_string()))?;
+        for (hash, file) in &overrides.files {
+            if let Some(start) = &file.start {
+                parse_start(start).ok_or_else(|| OverridesError::new(path, &format!("{}: bad start {}", hash, start)))?;
+            }
+            if let Some(device) = &file.device {
+                if !devices().iter().any(|d| d.name == device) {
+                    return Err(OverridesError::new(path, &format!("{}: no device profile called {}", hash, device)))
+                }
+            }
+        }
+        Ok(overrides)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), OverridesError> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| OverridesError::new(path, &e.to_string()))?;
+        fs::write(path, json + "\n").map_err(|e| OverridesError::new(path, &e.to_string()))
+    }
+
+    /// the override of the first part of `clip`, made when there isn't one yet
+    fn entry_for(&mut self, clip: &Clip) -> Option<&mut FileOverride> {
+        let hash = clip.first().content_hash()?;
+        let file = self.files.entry(hash).or_default();
+        if file.note.is_none() {
+            file.note = Some(clip.filename());
+        }
+        Some(file)
+    }
+
+    /// keeps `audio` out of the group of `video`
+    pub fn keep_out(&mut self, audio: &Clip, video: &Clip) {
+        let video_hash = match video.first().content_hash() {
+            Some(hash) => hash,
+            None => return,
+        };
+        if let Some(file) = self.entry_for(audio) {
+            if !file.not_in.contains(&video_hash) {
+                file.not_in.push(video_hash);
+            }
+        }
+    }
+
+    /// starts `audio` `seconds` after `video`, whatever its metadata says
+    pub fn pin_offset(&mut self, audio: &Clip, video: &Clip, seconds: f64) {
+        let video_hash = match video.first().content_hash() {
+            Some(hash) => hash,
+            None => return,
+        };
+        if let Some(file) = self.entry_for(audio) {
+            file.start = None;
+            file.offset = Some(RelativeOffset { from: video_hash, seconds });
+        }
+    }
+
+    fn for_file(&self, file: &MediaFile) -> Option<&FileOverride> {
+        if self.files.is_empty() {
+            return None
+        }
+        self.files.get(&file.content_hash()?)
+    }
+
+    /// the override of the first part of `clip` that has one
+    fn for_clip(&self, clip: &Clip) -> Option<&FileOverride> {
+        clip.parts.iter().find_map(|p| self.for_file(p))
+    }
+
+    fn is_file(&self, file: &MediaFile, hash: &str) -> bool {
+        file.content_hash().as_deref() == Some(hash)
+    }
+
+    /// `files` without the excluded ones
+    pub fn without_excluded(&self, files: Vec<MediaFile>) -> Vec<MediaFile> {
+        files.into_iter().filter(|f| !self.for_file(f).is_some_and(|o| o.exclude)).collect()
+    }
+
+    /// `clips` with the starts and devices set by hand. starts relative to another file follow that file's own corrected start
+    pub fn apply(&self, clips: Vec<Clip>) -> Vec<Clip> {
+        let clips: Vec<Clip> = clips
+            .into_iter()
+            .map(|clip| {
+                let (start, device) = match self.for_clip(&clip) {
+                    Some(o) => (o.start.as_deref().and_then(parse_start), o.device.clone()),
+                    None => return clip,
+                };
+                let clip = match start {
+                    Some(start) => clip.with_start(start),
+                    None => clip,
+                };
+                match device {
+                    Some(device) => clip.with_device(&device),
+                    None => clip,
+                }
+            })
+            .collect();
+        let relative_starts: Vec<Option<NaiveDateTime>> = clips
+            .iter()
+            .map(|clip| {
+                let offset = self.for_clip(clip)?.offset.as_ref()?;
+                let from = self.file_start(&clips, &offset.from)?;
+                Some(from + Duration::microseconds((offset.seconds * 1_000_000.).round() as i64))
+            })
+            .collect();
+        clips
+            .into_iter()
+            .zip(relative_starts)
+            .map(|(clip, start)| match start {
+                Some(start) => clip.with_start(start),
+                None => clip,
+            })
+            .collect()
+    }
+
+    /// start of the file with content `hash`, as part of whichever clip it's in
+    fn file_start(&self, clips: &[Clip], hash: &str) -> Option<NaiveDateTime> {
+        clips.iter().find_map(|clip| {
+            let index = clip.parts.iter().position(|p| self.is_file(p, hash))?;
+            let offset = clip.part_offsets()[index];
+            Some(clip.start()? + Duration::microseconds((offset * 1_000_000.).round() as i64))
+        })
+    }
+
+    /// whether `audio` goes with `video`: when they overlap, unless the overrides say otherwise
+    pub fn belongs(&self, video: &Clip, audio: &Clip) -> bool {
+        let is_video = |hash: &String| video.parts.iter().any(|p| self.is_file(p, hash));
+        match self.for_clip(audio) {
+            Some(FileOverride { group: Some(group), .. }) => is_video(group),
+            Some(o) => video.overlaps(audio) && !o.not_in.iter().any(is_video),
+            None => video.overlaps(audio),
+        }
+    }
+}
+
+fn parse_start(start: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(start, TIME_FORMAT).ok()
+}
+
+#[derive(Debug)]
+pub struct OverridesError {
+    path: PathBuf,
+    message: String,
+}
+
+impl OverridesError {
+    fn new(path: &Path, message: &str) -> Self {
+        Self { path: path.to_path_buf(), message: String::from(message) }
+    }
+}
+
+impl Display for OverridesError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "overrides {}: {}", self.path.display(), self.message)
+    }
+}
+
+impl Error for OverridesError {}
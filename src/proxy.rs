@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::daws::rpp::{RppNode, RppParam};
+use crate::daws::mlt::xml_escape;
+use crate::ffmpeg_wrapper::Ffmpeg;
+use crate::output::media_path;
+
+/// written into every proxy directory, maps proxy file names to the originals they were made from
+pub(crate) const PROXY_INDEX: &str = "smergy-proxies.json";
+
+/// edit-friendly codecs that decode fast when scrubbing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProxyCodec {
+    Mjpeg,
+    /// H.264 with every frame a keyframe
+    H264Intra,
+}
+
+impl ProxyCodec {
+    fn extension(&self) -> &'static str {
+        match self {
+            ProxyCodec::Mjpeg => "mov",
+            ProxyCodec::H264Intra => "mp4",
+        }
+    }
+
+    fn codec_args(&self) -> &'static [&'static str] {
+        match self {
+            ProxyCodec::Mjpeg => &["-c:v", "mjpeg", "-q:v", "4", "-pix_fmt", "yuvj420p", "-c:a", "pcm_s16le"],
+            ProxyCodec::H264Intra => &["-c:v", "libx264", "-preset", "veryfast", "-crf", "20", "-g", "1", "-bf", "0", "-c:a", "aac"],
+        }
+    }
+}
+
+impl FromStr for ProxyCodec {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "mjpeg" => Ok(ProxyCodec::Mjpeg),
+            "h264" | "h264-intra" => Ok(ProxyCodec::H264Intra),
+            other => Err(format!("unsupported proxy codec: {}", other)),
+        }
+    }
+}
+
+/// where proxies go and what they look like
+#[derive(Debug, Clone)]
+pub struct ProxySettings {
+    pub codec: ProxyCodec,
+    /// defaults to a `proxies` directory next to every original
+    pub directory: Option<PathBuf>,
+    /// appended to the original's file name, eg. `VID_0001_proxy.mov`
+    pub suffix: String,
+    pub height: u32,
+}
+
+impl ProxySettings {
+    pub fn new(codec: ProxyCodec) -> Self {
+        Self { codec, directory: None, suffix: String::from("_proxy"), height: 540 }
+    }
+
+    pub fn in_directory(mut self, directory: Option<PathBuf>) -> Self {
+        self.directory = directory;
+        self
+    }
+
+    pub fn with_suffix(mut self, suffix: &str) -> Self {
+        self.suffix = String::from(suffix);
+        self
+    }
+
+    pub fn proxy_path(&self, original: &Path) -> PathBuf {
+        let directory = match &self.directory {
+            Some(directory) => directory.clone(),
+            None => original.parent().unwrap_or_else(|| Path::new(".")).join("proxies"),
+        };
+        let stem = original.file_stem().and_then(|s| s.to_str()).unwrap_or("proxy");
+        directory.join(format!("{}{}.{}", stem, self.suffix, self.codec.extension()))
+    }
+
+    pub fn args(&self, original: &Path, proxy: &Path) -> Vec<String> {
+        let mut args: Vec<String> = vec![String::from("-y"), String::from("-i"), original.display().to_string()];
+        // -2 keeps the width even, which both encoders need
+        args.push(String::from("-vf"));
+        args.push(format!("scale=-2:{}", self.height));
+        args.extend(self.codec.codec_args().iter().map(|a| a.to_string()));
+        args.push(proxy.display().to_string());
+        args
+    }
+
+    /// makes the proxy of `original` unless it's already there, returning its path
+    pub fn generate(&self, original: &Path) -> Option<PathBuf> {
+        let proxy = self.proxy_path(original);
+        if !proxy.exists() {
+            fs::create_dir_all(proxy.parent()?).ok()?;
+            mark_proxy_directory(proxy.parent()?)?;
+            println!("# making proxy {}...", proxy.display());
+            // ffmpeg leaves a partial file behind when interrupted, it's written under a temporary name first
+            let partial = proxy.with_extension(format!("partial.{}", self.codec.extension()));
+            Ffmpeg::run(&self.args(original, &partial))?;
+            fs::rename(&partial, &proxy).ok()?;
+        }
+        remember_original(&proxy, original)?;
+        Some(proxy)
+    }
+}
+
+fn read_index(directory: &Path) -> HashMap<String, String> {
+    fs::read_to_string(directory.join(PROXY_INDEX))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// writes an empty index into `directory` unless it has one, scans skip directories with an index.
+/// done before encoding, so partial proxies of an interrupted run aren't taken for new videos either
+pub(crate) fn mark_proxy_directory(directory: &Path) -> Option<()> {
+    let index = directory.join(PROXY_INDEX);
+    match index.exists() {
+        true => Some(()),
+        false => fs::write(index, "{}").ok(),
+    }
+}
+
+pub(crate) fn remember_original(proxy: &Path, original: &Path) -> Option<()> {
+    let directory = proxy.parent()?;
+    let mut index = read_index(directory);
+    // relinking happens from wherever the project is, not from where the proxy was made
+    let original = fs::canonicalize(original).unwrap_or_else(|_| original.to_path_buf());
+    index.insert(proxy.file_name()?.to_str()?.to_string(), original.display().to_string());
+    fs::write(directory.join(PROXY_INDEX), serde_json::to_string_pretty(&index).ok()?).ok()
+}
+
+/// original a proxy was made from, looked up in the index next to the proxy. a relative `proxy` is taken from `directory`
+pub fn original_of(proxy: &str, directory: &Path) -> Option<String> {
+    let proxy = directory.join(proxy);
+    read_index(proxy.parent()?).remove(proxy.file_name()?.to_str()?)
+}
+
+/// the original of `proxy` as a project in `directory` refers to it, relative to the project when the proxy was
+fn relinked_path(proxy: &str, directory: &Path) -> Option<String> {
+    let original = original_of(proxy, directory)?;
+    match Path::new(proxy).is_absolute() {
+        true => Some(original),
+        false => Some(media_path(&original, directory)),
+    }
+}
+
+/// points every media source of a reaper project in `directory` that is a known proxy back at its original, returns how many
+pub fn relink_rpp(node: &mut RppNode, directory: &Path) -> usize {
+    let mut relinked = 0;
+    if node.name == "FILE" {
+        if let Some(original) = node.param_value(0).and_then(|p| relinked_path(p, directory)) {
+            node.params[0] = RppParam::Quoted(original);
+            relinked += 1;
+        }
+    }
+    for child in node.children.iter_mut() {
+        relinked += relink_rpp(child, directory);
+    }
+    relinked
+}
+
+/// same for the resources of an MLT project
+pub fn relink_mlt(project: &str, directory: &Path) -> (String, usize) {
+    let mut relinked = 0;
+    let lines = project.lines().map(|line| {
+        let resource = line
+            .trim()
+            .strip_prefix("<property name=\"resource\">")
+            .and_then(|r| r.strip_suffix("</property>"));
+        match resource.and_then(|r| relinked_path(&xml_unescape(r), directory)) {
+            Some(original) => {
+                relinked += 1;
+                line.replace(resource.unwrap_or_default(), &xml_escape(&original))
+            },
+            None => String::from(line),
+        }
+    });
+    let mut relinked_project = lines.collect::<Vec<_>>().join("\n");
+    if project.ends_with('\n') {
+        relinked_project.push('\n');
+    }
+    (relinked_project, relinked)
+}
+
+fn xml_unescape(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// proxies for the `originals`, by original path. files whose proxy couldn't be made are left out
+pub fn make_proxies<'a, I: IntoIterator<Item = &'a Path>>(settings: &ProxySettings, originals: I) -> HashMap<String, String> {
+    originals
+        .into_iter()
+        .filter_map(|original| {
+            let proxy = settings.generate(original)?;
+            Some((original.display().to_string(), proxy.display().to_string()))
+        })
+        .collect()
+}
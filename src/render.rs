@@ -0,0 +1,221 @@
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::fs;
+
+use crate::clip::{Clip, seconds};
+use crate::devices::device_mix;
+use crate::ffmpeg_wrapper::Ffmpeg;
+use crate::loudness::{Loudness, LOUDNORM_ANALYSIS};
+use crate::mix::{MixRule, MixSource, mix_filter, settings_for};
+
+/// codec the external audio is encoded to, the container of the rendered file follows from it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioCodec {
+    Aac,
+    Pcm,
+    Flac,
+}
+
+impl AudioCodec {
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "aac",
+            AudioCodec::Pcm => "pcm_s24le",
+            AudioCodec::Flac => "flac",
+        }
+    }
+
+    /// mp4 can't hold pcm, and flac in mp4 is poorly supported by players
+    pub fn container(&self) -> &'static str {
+        match self {
+            AudioCodec::Aac => "mp4",
+            AudioCodec::Pcm => "mov",
+            AudioCodec::Flac => "mkv",
+        }
+    }
+}
+
+impl FromStr for AudioCodec {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "aac" => Ok(AudioCodec::Aac),
+            "pcm" | "wav" => Ok(AudioCodec::Pcm),
+            "flac" => Ok(AudioCodec::Flac),
+            other => Err(format!("unsupported audio codec: {}", other)),
+        }
+    }
+}
+
+/// camera video with a mixdown of the external recorders, muxed by ffmpeg without re-encoding the video
+#[derive(Debug, Clone)]
+pub struct Render {
+    video: Vec<PathBuf>,
+    sources: Vec<MixSource>,
+    /// length of the video, the mixdown gets trimmed or padded to it
+    span: f64,
+    codec: AudioCodec,
+    keep_camera_audio: bool,
+    /// whether the video has audio of its own that can be kept
+    camera_audio: bool,
+    /// integrated loudness in LUFS the mixdown is normalized to
+    loudness_target: Option<f64>,
+    output: PathBuf,
+}
+
+impl Render {
+    pub fn new(video: Vec<PathBuf>, sources: Vec<MixSource>, span: f64, output: PathBuf) -> Self {
+        Self { video, sources, span, codec: AudioCodec::Aac, keep_camera_audio: false, camera_audio: true, loudness_target: None, output }
+    }
+
+    pub fn with_codec(mut self, codec: AudioCodec) -> Self {
+        self.output.set_extension(codec.container());
+        self.codec = codec;
+        self
+    }
+
+    /// keeps the camera's own audio as a second audio track
+    pub fn keeping_camera_audio(mut self, keep: bool) -> Self {
+        self.keep_camera_audio = keep;
+        self
+    }
+
+    /// whether the video has an audio stream, there's nothing to keep when it doesn't
+    pub fn with_camera_audio(mut self, camera_audio: bool) -> Self {
+        self.camera_audio = camera_audio;
+        self
+    }
+
+    /// normalizes the mixdown to `target` LUFS in two passes, measuring it first
+    pub fn normalized_to(mut self, target: Option<f64>) -> Self {
+        self.loudness_target = target;
+        self
+    }
+
+    /// renders the video with a mixdown of all the audio recorded alongside it, into the current directory.
+    /// every clip is mixed according to `rules`, falling back to the profile of its device
+    pub fn from_group(video: &Clip, audios: &[Clip], rules: &[MixRule]) -> Option<Self> {
+        let video_start = video.start()?;
+        let sources: Vec<MixSource> = audios
+            .iter()
+            .filter_map(|audio| {
+                let device = audio.device().unwrap_or("unknown");
+                let settings = settings_for(rules, &audio.filename(), device, device_mix(device));
+                Some(MixSource::new(
+                    audio.parts.iter().map(|p| p.entry.path().to_path_buf()).collect(),
+                    seconds(audio.start()? - video_start),
+                ).with_settings(settings).with_drift(audio.drift_ppm))
+            })
+            .collect();
+        if sources.iter().all(|s| s.settings.mute) {
+            return None
+        }
+        let stem = video.first().filename().replace('.', "_");
+        Some(Self::new(
+            video.parts.iter().map(|p| p.entry.path().to_path_buf()).collect(),
+            sources,
+            seconds(video.duration()?),
+            PathBuf::from(format!("{}_synced.mp4", stem)),
+        ).with_camera_audio(video.first().channels().is_some()))
+    }
+
+    pub fn output(&self) -> &Path {
+        &self.output
+    }
+
+    /// concat demuxer list joining the video parts, ffmpeg can copy the video stream through it.
+    /// ffmpeg resolves the files against the list's directory, so they're written absolute
+    pub fn concat_list(&self) -> String {
+        self.video
+            .iter()
+            .map(|p| fs::canonicalize(p).unwrap_or_else(|_| p.clone()))
+            .map(|p| format!("file '{}'\n", p.display().to_string().replace('\'', r"'\''")))
+            .collect()
+    }
+
+    /// where the concat list is written while rendering
+    fn concat_list_path(&self) -> PathBuf {
+        let stem = self.output.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| String::from("render"));
+        std::env::temp_dir().join(format!("{}-{}.concat", stem, std::process::id()))
+    }
+
+    /// filter graph mixing the external audio and lining it up with the video as `[external]`
+    pub fn filter(&self) -> String {
+        mix_filter(&self.sources, 1, self.span)
+    }
+
+    /// ffmpeg arguments, `video_input` being the single video file or the concat list of its parts.
+    /// `filter` ends in `[external]`, the audio written next to the video
+    pub fn args(&self, video_input: &Path, filter: &str) -> Vec<String> {
+        let mut args: Vec<String> = vec![String::from("-y")];
+        args.extend(self.inputs(video_input));
+        args.extend([
+            "-filter_complex", filter,
+            "-map", "0:v:0", "-c:v", "copy",
+            "-map", "[external]",
+        ].iter().map(|a| a.to_string()));
+        args.extend(["-c:a:0", self.codec.encoder(), "-metadata:s:a:0", "title=external"].iter().map(|a| a.to_string()));
+        // the camera's track is copied as it is, only the mixdown is encoded
+        if self.keep_camera_audio && self.camera_audio {
+            args.extend(["-map", "0:a:0", "-c:a:1", "copy", "-metadata:s:a:1", "title=camera"].iter().map(|a| a.to_string()));
+        }
+        args.extend(["-t", &format!("{:.6}", self.span)].iter().map(|a| a.to_string()));
+        args.push(self.output.display().to_string());
+        args
+    }
+
+    fn inputs(&self, video_input: &Path) -> Vec<String> {
+        let mut args = vec![];
+        if self.video.len() > 1 {
+            args.extend(["-f", "concat", "-safe", "0"].iter().map(|a| a.to_string()));
+        }
+        args.push(String::from("-i"));
+        args.push(video_input.display().to_string());
+        for audio in self.sources.iter().flat_map(|s| s.audio.iter()) {
+            args.push(String::from("-i"));
+            args.push(audio.display().to_string());
+        }
+        args
+    }
+
+    /// first pass of the normalization, measuring the mixdown without writing anything
+    fn measure(&self, video_input: &Path) -> Option<Loudness> {
+        let mut args = self.inputs(video_input);
+        let filter = format!("{};[external]{}", self.filter(), LOUDNORM_ANALYSIS);
+        args.extend(["-filter_complex", &filter, "-f", "null", "-"].iter().map(|a| a.to_string()));
+        Loudness::from_loudnorm_output(&Ffmpeg::analyze(&args)?)
+    }
+
+    /// the mix filter, followed by the second loudnorm pass when normalizing
+    fn final_filter(&self, video_input: &Path) -> Option<String> {
+        let target = match self.loudness_target {
+            Some(target) => target,
+            None => return Some(self.filter()),
+        };
+        let measured = self.measure(video_input)?;
+        println!("# mixdown of {}: {}, normalizing to {} LUFS", self.output.display(), measured, target);
+        // loudnorm works at 192kHz internally and outputs that rate unless told otherwise
+        Some(format!(
+            "{}[mix];[mix]{},aresample=48000[external]",
+            self.filter().trim_end_matches("[external]"),
+            measured.loudnorm_filter(target),
+        ))
+    }
+
+    fn render(&self, video_input: &Path) -> Option<()> {
+        Ffmpeg::run(&self.args(video_input, &self.final_filter(video_input)?))
+    }
+
+    pub fn run(&self) -> Option<()> {
+        match self.video.len() {
+            1 => self.render(&self.video[0]),
+            _ => {
+                let list = self.concat_list_path();
+                fs::write(&list, self.concat_list()).ok()?;
+                let rendered = self.render(&list);
+                let _ = fs::remove_file(&list);
+                rendered
+            },
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Duration};
+use serde::Serialize;
+
+use crate::clip::Clip;
+use crate::ffmpeg_wrapper::RawStream;
+use crate::media_file::{MediaFile, MediaType};
+
+/// bumped whenever a field of the machine readable output is removed or changes meaning, new fields don't bump it
+pub const REPORT_VERSION: u32 = 1;
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6f";
+
+const CSV_HEADER: [&str; 9] = ["group", "path", "type", "duration", "start", "end", "device", "timing_source", "streams"];
+
+/// how scan and plan print what they found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            other => Err(format!("unsupported output format: {}", other)),
+        }
+    }
+}
+
+/// every file of a scan or plan, for other tools to read
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Report {
+    pub version: u32,
+    pub files: Vec<FileRecord>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileRecord {
+    /// content hash of the video whose group the file is in, none when not grouped
+    pub group: Option<String>,
+    pub path: String,
+    /// `video` or `audio`
+    #[serde(rename = "type")]
+    pub media_type: Option<String>,
+    /// seconds
+    pub duration: Option<f64>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub device: Option<String>,
+    pub timing_source: String,
+    pub streams: Vec<StreamRecord>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StreamRecord {
+    pub index: u32,
+    #[serde(rename = "type")]
+    pub stream_type: Option<String>,
+    pub codec: Option<String>,
+    pub channels: Option<u16>,
+    pub sample_rate: Option<u32>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub frame_rate: Option<String>,
+}
+
+impl Report {
+    /// every part of every clip, timed as its clip is. each clip comes with the video of its group, if it's in one
+    pub fn from_clips<'a>(clips: impl IntoIterator<Item = (&'a Clip, Option<&'a Clip>)>) -> Self {
+        let mut files = vec![];
+        for (clip, group) in clips {
+            let group = group.and_then(|g| g.first().content_hash());
+            let start = clip.start();
+            for (part, offset) in clip.parts.iter().zip(clip.part_offsets()) {
+                let part_start = start.map(|s| s + Duration::microseconds((offset * 1_000_000.).round() as i64));
+                files.push(FileRecord::new(part, part_start, clip, group.clone()));
+            }
+        }
+        Self { version: REPORT_VERSION, files }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// one line per file, streams joined with `; `
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec![CSV_HEADER.join(",")];
+        for file in &self.files {
+            let fields = [
+                file.group.clone().unwrap_or_default(),
+                file.path.clone(),
+                file.media_type.clone().unwrap_or_default(),
+                file.duration.map(|d| format!("{:.6}", d)).unwrap_or_default(),
+                file.start.clone().unwrap_or_default(),
+                file.end.clone().unwrap_or_default(),
+                file.device.clone().unwrap_or_default(),
+                file.timing_source.clone(),
+                file.streams.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("; "),
+            ];
+            lines.push(fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        }
+        lines.join("\n") + "\n"
+    }
+}
+
+impl FileRecord {
+    fn new(file: &MediaFile, start: Option<NaiveDateTime>, clip: &Clip, group: Option<String>) -> Self {
+        let duration = file.exact_duration();
+        let end = match (start, duration) {
+            (Some(start), Some(duration)) => Some(start + Duration::microseconds((duration * 1_000_000.).round() as i64)),
+            _ => None,
+        };
+        Self {
+            group,
+            path: String::from(file.full_path()),
+            media_type: file.media_type().map(|t| String::from(match t {
+                MediaType::Video => "video",
+                MediaType::Audio => "audio",
+            })),
+            duration,
+            start: start.map(|s| s.format(TIME_FORMAT).to_string()),
+            end: end.map(|e| e.format(TIME_FORMAT).to_string()),
+            device: clip.device().map(String::from),
+            timing_source: clip.timing_source(),
+            streams: file.ffmpeg_data_raw().map(|d| d.streams.iter().map(StreamRecord::new).collect()).unwrap_or_default(),
+        }
+    }
+}
+
+impl StreamRecord {
+    fn new(stream: &RawStream) -> Self {
+        Self {
+            index: stream.index,
+            stream_type: stream.codec_type.clone(),
+            codec: stream.codec_name.clone(),
+            channels: stream.channels,
+            sample_rate: stream.sample_rate.as_ref().and_then(|r| r.parse().ok()),
+            width: stream.width,
+            height: stream.height,
+            frame_rate: stream.r_frame_rate.clone().filter(|_| stream.codec_type.as_deref() == Some("video")),
+        }
+    }
+}
+
+impl Display for StreamRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.codec.as_deref().unwrap_or("unknown"))?;
+        if let (Some(width), Some(height)) = (self.width, self.height) {
+            write!(f, " {}x{}", width, height)?;
+        }
+        if let Some(frame_rate) = &self.frame_rate {
+            write!(f, " {}fps", frame_rate)?;
+        }
+        if let Some(channels) = self.channels {
+            write!(f, " {}ch", channels)?;
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            write!(f, " {}Hz", sample_rate)?;
+        }
+        Ok(())
+    }
+}
+
+/// quoted when it holds a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => String::from(field),
+    }
+}
@@ -0,0 +1,493 @@
+use std::io::{self, BufRead, Read, Write};
+use std::iter::once;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::clip::{Clip, seconds};
+use crate::manifest::Manifest;
+use crate::overrides::Overrides;
+use crate::select::VideoAudioGroup;
+use crate::terminal::{Key, CLEAR, parse_keys};
+
+pub const DEFAULT_PLAYER: &str = "ffplay -nodisp -autoexit -loglevel quiet -ss {start} -t {length} {file}";
+
+const HELP: &str = "\
+l              list the group again
+t N            toggle recording N in or out of the group
+n N SECONDS    nudge recording N later (or earlier when negative)
+p N|v SECONDS  play recording N or the video from SECONDS into the video
+a              approve the group and go to the next one
+r              reject the group and go to the next one
+>, <           next or previous group, leaving it undecided
+q              stop reviewing";
+
+/// keys of the full-screen view
+const KEYS: &str = "\
+up, down, j, k     select a recording
+space, t           toggle the recording in or out of the group
+left, right, h, l  nudge the recording 0.1s earlier or later
+shift+left/right   nudge it by a second, H and L too
+[, ]               snippets play from a snippet earlier or later into the video
+p, v               play the recording or the video
+a, r               approve or reject the group and go to the next one
+n, b               next or previous group, leaving it undecided
+q, esc             stop reviewing";
+
+/// seconds a recording moves with left and right, and with shift held
+const NUDGE_STEP: f64 = 0.1;
+const NUDGE_STEP_LARGE: f64 = 1.;
+
+/// what can be typed at the review prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReviewCommand {
+    List,
+    Toggle(usize),
+    Nudge(usize, f64),
+    /// a recording, or the video when none, from seconds into the video
+    Play(Option<usize>, f64),
+    Approve,
+    Reject,
+    Next,
+    Previous,
+    Quit,
+    Help,
+}
+
+impl FromStr for ReviewCommand {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let index = |word: &str| word.parse::<usize>().map_err(|_| format!("not a recording number: {}", word));
+        let seconds = |word: &str| word.parse::<f64>().map_err(|_| format!("not a number of seconds: {}", word));
+        match words.as_slice() {
+            ["l"] | ["list"] => Ok(ReviewCommand::List),
+            ["t", n] | ["toggle", n] => Ok(ReviewCommand::Toggle(index(n)?)),
+            ["n", n, by] | ["nudge", n, by] => Ok(ReviewCommand::Nudge(index(n)?, seconds(by)?)),
+            ["p", "v", at] | ["play", "v", at] => Ok(ReviewCommand::Play(None, seconds(at)?)),
+            ["p", n, at] | ["play", n, at] => Ok(ReviewCommand::Play(Some(index(n)?), seconds(at)?)),
+            ["a"] | ["approve"] => Ok(ReviewCommand::Approve),
+            ["r"] | ["reject"] => Ok(ReviewCommand::Reject),
+            [">"] | ["next"] => Ok(ReviewCommand::Next),
+            ["<"] | ["previous"] => Ok(ReviewCommand::Previous),
+            ["q"] | ["quit"] => Ok(ReviewCommand::Quit),
+            ["?"] | ["h"] | ["help"] => Ok(ReviewCommand::Help),
+            _ => Err(format!("unknown command: {}, ? lists them", s.trim())),
+        }
+    }
+}
+
+/// a group as it's being reviewed
+struct ReviewedGroup {
+    video: Clip,
+    audios: Vec<Clip>,
+    included: Vec<bool>,
+    nudged: Vec<bool>,
+    approved: Option<bool>,
+}
+
+/// what a command did to the review
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+    Changed,
+    Unchanged,
+    /// nothing changed, and why
+    Message(String),
+    /// no more groups to go through
+    Done(Option<String>),
+}
+
+/// goes through the groups one by one to correct them before anything is exported,
+/// in a full-screen view of the terminal or at a line prompt when the commands are piped in
+pub struct Review {
+    groups: Vec<ReviewedGroup>,
+    current: usize,
+    /// recording the keys act on in the full-screen view
+    selected: usize,
+    /// seconds into the video snippets are played from in the full-screen view
+    play_at: f64,
+    player: String,
+    snippet: f64,
+}
+
+impl Review {
+    /// `approvals` are the decisions made in an earlier review, by group
+    pub fn new(groups: Vec<VideoAudioGroup>, approvals: &[Option<bool>]) -> Self {
+        let groups = groups
+            .into_iter()
+            .enumerate()
+            .map(|(index, (video, audios))| ReviewedGroup {
+                included: vec![true; audios.len()],
+                nudged: vec![false; audios.len()],
+                approved: approvals.get(index).copied().flatten(),
+                video,
+                audios,
+            })
+            .collect();
+        Self { groups, current: 0, selected: 0, play_at: 0., player: String::from(DEFAULT_PLAYER), snippet: 5. }
+    }
+
+    /// `player` is run with {file}, {start} and {length} filled in to play `snippet` seconds
+    pub fn with_player(mut self, player: &str, snippet: f64) -> Self {
+        self.player = String::from(player);
+        self.snippet = snippet;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// reads commands from `input` until the last group is decided, quit or the end of the input
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        if self.groups.is_empty() {
+            return writeln!(output, "# no groups to review")
+        }
+        writeln!(output, "{}\n", HELP)?;
+        self.list(&mut output)?;
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match line.parse::<ReviewCommand>() {
+                Ok(command) => command,
+                Err(error) => {
+                    writeln!(output, "# {}", error)?;
+                    continue;
+                },
+            };
+            match command {
+                ReviewCommand::List => {},
+                ReviewCommand::Help => writeln!(output, "{}", HELP)?,
+                command => match self.apply(command) {
+                    Outcome::Changed => {},
+                    Outcome::Unchanged => continue,
+                    Outcome::Message(message) => {
+                        writeln!(output, "# {}", message)?;
+                        continue;
+                    },
+                    Outcome::Done(message) => {
+                        if let Some(message) = message {
+                            writeln!(output, "# {}", message)?;
+                        }
+                        return Ok(())
+                    },
+                },
+            }
+            self.list(&mut output)?;
+        }
+        Ok(())
+    }
+
+    /// takes keys from `input` in raw mode, drawing the review on a screen of `size` (rows, columns) after each one,
+    /// until the last group is decided, quit or the end of the input
+    pub fn run_screen(&mut self, mut input: impl Read, mut output: impl Write, size: (usize, usize)) -> io::Result<()> {
+        if self.groups.is_empty() {
+            return Ok(())
+        }
+        let mut status = String::new();
+        let mut help = false;
+        let mut buffer = [0u8; 64];
+        loop {
+            let screen = self.screen(size, &status, help).replace('\n', "\r\n");
+            write!(output, "{}{}", CLEAR, screen)?;
+            output.flush()?;
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(())
+            }
+            for key in parse_keys(&buffer[..read]) {
+                status.clear();
+                let command = match key {
+                    Key::Char('?') => {
+                        help = !help;
+                        continue;
+                    },
+                    key => match self.key_command(key) {
+                        Some(command) => command,
+                        None => continue,
+                    },
+                };
+                match self.apply(command) {
+                    Outcome::Changed | Outcome::Unchanged => {},
+                    Outcome::Message(message) => status = message,
+                    Outcome::Done(_) => return Ok(()),
+                }
+            }
+        }
+    }
+
+    /// what `key` does in the full-screen view, moving the selection and the snippet start itself
+    fn key_command(&mut self, key: Key) -> Option<ReviewCommand> {
+        let recordings = self.groups[self.current].audios.len();
+        let selected = self.selected;
+        match key {
+            Key::Up | Key::Char('k') => self.selected = selected.saturating_sub(1),
+            Key::Down | Key::Char('j') => self.selected = (selected + 1).min(recordings.saturating_sub(1)),
+            Key::Char('[') => self.play_at = (self.play_at - self.snippet).max(0.),
+            Key::Char(']') => self.play_at += self.snippet,
+            Key::Char(' ') | Key::Char('t') => return Some(ReviewCommand::Toggle(selected)),
+            Key::Left | Key::Char('h') => return Some(ReviewCommand::Nudge(selected, -NUDGE_STEP)),
+            Key::Right | Key::Char('l') => return Some(ReviewCommand::Nudge(selected, NUDGE_STEP)),
+            Key::ShiftLeft | Key::Char('H') => return Some(ReviewCommand::Nudge(selected, -NUDGE_STEP_LARGE)),
+            Key::ShiftRight | Key::Char('L') => return Some(ReviewCommand::Nudge(selected, NUDGE_STEP_LARGE)),
+            Key::Char('p') => return Some(ReviewCommand::Play(Some(selected), self.play_at)),
+            Key::Char('v') => return Some(ReviewCommand::Play(None, self.play_at)),
+            Key::Char('a') => return Some(ReviewCommand::Approve),
+            Key::Char('r') => return Some(ReviewCommand::Reject),
+            Key::Char('n') | Key::Char('\t') | Key::Char('>') => return Some(ReviewCommand::Next),
+            Key::Char('b') | Key::Char('<') => return Some(ReviewCommand::Previous),
+            Key::Char('q') | Key::Escape => return Some(ReviewCommand::Quit),
+            _ => {},
+        }
+        None
+    }
+
+    /// carries out a command on the current group
+    fn apply(&mut self, command: ReviewCommand) -> Outcome {
+        let group = &mut self.groups[self.current];
+        match command {
+            ReviewCommand::List | ReviewCommand::Help => return Outcome::Unchanged,
+            ReviewCommand::Toggle(n) | ReviewCommand::Nudge(n, _) if n >= group.audios.len() => {
+                return Outcome::Message(format!("there's no recording {}", n))
+            },
+            ReviewCommand::Toggle(n) => group.included[n] = !group.included[n],
+            ReviewCommand::Nudge(n, by) => {
+                let start = match group.audios[n].start() {
+                    Some(start) => start,
+                    None => return Outcome::Message(format!("recording {} has no start to nudge", n)),
+                };
+                let nudged = group.audios[n].clone().with_start(start + Duration::microseconds((by * 1_000_000.).round() as i64));
+                group.audios[n] = nudged;
+                group.nudged[n] = true;
+            },
+            ReviewCommand::Play(n, at) => return match self.play(n, at) {
+                Some(message) => Outcome::Message(message),
+                None => Outcome::Unchanged,
+            },
+            ReviewCommand::Approve | ReviewCommand::Reject => {
+                group.approved = Some(command == ReviewCommand::Approve);
+                if self.current + 1 == self.groups.len() {
+                    return Outcome::Done(Some(String::from("that was the last group")))
+                }
+                self.show(self.current + 1);
+            },
+            ReviewCommand::Next => self.show((self.current + 1).min(self.groups.len() - 1)),
+            ReviewCommand::Previous => self.show(self.current.saturating_sub(1)),
+            ReviewCommand::Quit => return Outcome::Done(None),
+        }
+        Outcome::Changed
+    }
+
+    /// moves on to group `index`
+    fn show(&mut self, index: usize) {
+        if index != self.current {
+            self.current = index;
+            self.selected = 0;
+            self.play_at = 0.;
+        }
+    }
+
+    fn list(&self, output: &mut impl Write) -> io::Result<()> {
+        let group = &self.groups[self.current];
+        writeln!(output, "\n# group {}/{}, {}: {}", self.current + 1, self.groups.len(), decision(group.approved), group.video)?;
+        for (index, audio) in group.audios.iter().enumerate() {
+            let offset = match (audio.start(), group.video.start()) {
+                (Some(start), Some(video_start)) => format!("{:+.3}s", seconds(start - video_start)),
+                _ => String::from("unknown"),
+            };
+            let included = match group.included[index] {
+                true => "x",
+                false => " ",
+            };
+            writeln!(output, "[{}] {:>3} {:>12}  {}", included, index, offset, audio)?;
+        }
+        Ok(())
+    }
+
+    /// the full-screen view: the groups, the recordings of the current one drawn against its video, `status` and the keys
+    pub fn screen(&self, (rows, columns): (usize, usize), status: &str, help: bool) -> String {
+        let group = &self.groups[self.current];
+        let fit = |line: String| line.chars().take(columns).collect::<String>();
+        let mut lines = vec![fit(format!(
+            "review: group {}/{}, {}, snippets from {:.1}s into the video",
+            self.current + 1,
+            self.groups.len(),
+            decision(group.approved),
+            self.play_at,
+        ))];
+        lines.push(String::new());
+
+        // as many of the groups around the current one as leave room for the recordings
+        let room = rows.saturating_sub(group.audios.len() + 9).clamp(1, 10);
+        let first = self.current.saturating_sub(room / 2).min(self.groups.len().saturating_sub(room));
+        for (index, reviewed) in self.groups.iter().enumerate().skip(first).take(room) {
+            let marker = match index == self.current {
+                true => '>',
+                false => ' ',
+            };
+            let decided = match reviewed.approved {
+                Some(true) => '+',
+                Some(false) => '-',
+                None => ' ',
+            };
+            lines.push(fit(format!("{} {} {:>3}  {}", marker, decided, index + 1, reviewed.video)));
+        }
+        lines.push(String::new());
+
+        let label_width = 40;
+        let bar_width = columns.saturating_sub(label_width + 2).max(10);
+        let bars = Bars::of(group, bar_width, self.play_at);
+        lines.push(fit(format!("{:<width$}|{}|", format!("      video {}", group.video.filename()), bars.video, width = label_width)));
+        for (index, audio) in group.audios.iter().enumerate() {
+            let cursor = match index == self.selected {
+                true => '>',
+                false => ' ',
+            };
+            let included = match group.included[index] {
+                true => 'x',
+                false => ' ',
+            };
+            let offset = match (audio.start(), group.video.start()) {
+                (Some(start), Some(video_start)) => format!("{:+.3}s", seconds(start - video_start)),
+                _ => String::from("unknown"),
+            };
+            let label = format!("{}[{}] {:>2} {:<20.20} {:>10}", cursor, included, index, audio.filename(), offset);
+            lines.push(fit(format!("{:<width$}|{}|", label, bars.audios[index], width = label_width)));
+        }
+        lines.push(String::new());
+        if !status.is_empty() {
+            lines.push(fit(format!("# {}", status)));
+        }
+        match help {
+            true => lines.extend(KEYS.lines().map(|l| fit(String::from(l)))),
+            false => lines.push(fit(String::from("? keys, space toggle, left/right nudge, p play, a approve, r reject, q quit"))),
+        }
+        lines.join("\n")
+    }
+
+    /// plays a snippet of a recording, or of the video, from `at` seconds into the video. says why when it can't
+    fn play(&self, recording: Option<usize>, at: f64) -> Option<String> {
+        let group = &self.groups[self.current];
+        let clip = match recording {
+            Some(n) => match group.audios.get(n) {
+                Some(audio) => audio,
+                None => return Some(format!("there's no recording {}", n)),
+            },
+            None => &group.video,
+        };
+        let offset = match (clip.start(), group.video.start()) {
+            (Some(start), Some(video_start)) => seconds(start - video_start),
+            _ => 0.,
+        };
+        let (part, position) = match clip.part_at(at - offset) {
+            Some(found) => found,
+            None => return Some(format!("{} isn't recording at {}s", clip.filename(), at)),
+        };
+        let args = player_args(&self.player, part.entry.path(), position, self.snippet);
+        if args.is_empty() {
+            return Some(String::from("no player command"))
+        }
+        match Command::new(&args[0]).args(&args[1..]).stdin(Stdio::null()).status() {
+            Ok(status) if status.success() => None,
+            Ok(status) => Some(format!("{} exited with {}", args[0], status)),
+            Err(error) => Some(format!("can't run {}: {}", args[0], error)),
+        }
+    }
+
+    /// the groups as reviewed, without the recordings toggled out
+    pub fn groups(&self) -> Vec<VideoAudioGroup> {
+        self.groups
+            .iter()
+            .map(|g| {
+                let audios = g.audios.iter().zip(&g.included).filter(|(_, included)| **included).map(|(a, _)| a.clone()).collect();
+                (g.video.clone(), audios)
+            })
+            .collect()
+    }
+
+    /// a manifest of the reviewed groups with the decisions made
+    pub fn manifest(&self) -> Manifest {
+        let mut manifest = Manifest::from_groups(&self.groups());
+        for (saved, group) in manifest.groups.iter_mut().zip(&self.groups) {
+            saved.approved = group.approved;
+        }
+        manifest
+    }
+
+    /// records the recordings toggled out and the nudged offsets in `overrides`, approvals only go in manifests
+    pub fn update_overrides(&self, overrides: &mut Overrides) {
+        for group in &self.groups {
+            for (index, audio) in group.audios.iter().enumerate() {
+                if !group.included[index] {
+                    overrides.keep_out(audio, &group.video);
+                    continue;
+                }
+                if let (true, Some(start), Some(video_start)) = (group.nudged[index], audio.start(), group.video.start()) {
+                    overrides.pin_offset(audio, &group.video, seconds(start - video_start));
+                }
+            }
+        }
+    }
+}
+
+fn decision(approved: Option<bool>) -> &'static str {
+    match approved {
+        Some(true) => "approved",
+        Some(false) => "rejected",
+        None => "undecided",
+    }
+}
+
+/// the recordings of a group as bars on a shared time axis, `│` where snippets play from
+struct Bars {
+    video: String,
+    audios: Vec<String>,
+}
+
+impl Bars {
+    fn of(group: &ReviewedGroup, width: usize, play_at: f64) -> Self {
+        let clips = once(&group.video).chain(group.audios.iter());
+        let from = clips.clone().filter_map(|c| c.start()).min();
+        let to = clips.filter_map(|c| c.end()).max();
+        let after = |time: NaiveDateTime, seconds: f64| time + Duration::microseconds((seconds * 1_000_000.).round() as i64);
+        let draw = |clip: &Clip, included: bool| -> String {
+            let (from, to, start, end) = match (from, to, clip.start(), clip.end()) {
+                (Some(from), Some(to), Some(start), Some(end)) if to > from => (from, to, start, end),
+                _ => return "?".repeat(width),
+            };
+            let span = seconds(to - from);
+            let playhead = group.video.start().map(|s| (seconds(after(s, play_at) - from) / span * width as f64) as usize);
+            (0..width)
+                .map(|column| {
+                    let at = after(from, span * (column as f64 + 0.5) / width as f64);
+                    match (Some(column) == playhead, start <= at && at < end, included) {
+                        (true, _, _) => '│',
+                        (_, true, true) => '█',
+                        (_, true, false) => '░',
+                        _ => '·',
+                    }
+                })
+                .collect()
+        };
+        Self {
+            video: draw(&group.video, true),
+            audios: group.audios.iter().zip(&group.included).map(|(audio, included)| draw(audio, *included)).collect(),
+        }
+    }
+}
+
+/// the player command split into arguments, with the snippet filled in
+pub fn player_args(template: &str, file: &Path, start: f64, length: f64) -> Vec<String> {
+    template
+        .split_whitespace()
+        .map(|arg| {
+            arg.replace("{start}", &format!("{:.3}", start))
+                .replace("{length}", &format!("{:.3}", length))
+                .replace("{file}", &file.display().to_string())
+        })
+        .collect()
+}
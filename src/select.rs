@@ -1,24 +1,24 @@
-use crate::media_file::{MediaFile, media_files, MediaType};
+use crate::clip::Clip;
+use crate::dedup::deduplicate;
+use crate::media_file::{MediaFile, walked_media_files, MediaType};
+use crate::overrides::Overrides;
+use crate::walk::WalkFilter;
 use std::path::Path;
 
 pub struct Select {}
-pub type VideoAudioGroup = (MediaFile, Vec<MediaFile>);
+pub type VideoAudioGroup = (Clip, Vec<Clip>);
 
 impl Select {
-    fn media_files<T: AsRef<Path>>(directories: &Vec<T>) -> Vec<MediaFile> {
-        directories
-            .iter()
-            .map(media_files)
-            .flatten()
-            .into_iter()
-            .collect()
+    /// every media file found in `directories` that `filter` lets through, identical copies found in several places counted once
+    pub fn media_files<T: AsRef<Path>>(directories: &[T], filter: &WalkFilter) -> Vec<MediaFile> {
+        deduplicate(directories.iter().flat_map(|d| walked_media_files(d, filter)).collect())
     }
 
-    /// returns tuples of (VideoFiles, AudioFiles) who have some overlap
-    fn media_files_by_type(media_files: Vec<MediaFile>) -> (Vec<MediaFile>, Vec<MediaFile>) {
+    /// returns tuples of (VideoClips, AudioClips) who have some overlap
+    fn clips_by_type(clips: Vec<Clip>) -> (Vec<Clip>, Vec<Clip>) {
         let mut video = vec![];
         let mut audio = vec![];
-        for file in media_files {
+        for file in clips {
             let media_type = match file.media_type() {
                 Some(t) => t,
                 None => { continue; }
@@ -31,26 +31,30 @@ impl Select {
         (video, audio)
     }
 
-    fn grouped_media<T: AsRef<Path>>(directories: &Vec<T>) -> Vec<VideoAudioGroup> {
-        let all_media = Self::media_files(directories);
-        let (video_files, audio_files ) = Self::media_files_by_type(all_media);
+    /// clips of the scanned `files`, with `overrides` applied
+    pub fn clips(files: Vec<MediaFile>, overrides: &Overrides) -> Vec<Clip> {
+        overrides.apply(Clip::from_files(overrides.without_excluded(files)))
+    }
+
+    fn grouped_media(clips: Vec<Clip>, overrides: &Overrides) -> Vec<VideoAudioGroup> {
+        let (video_files, audio_files ) = Self::clips_by_type(clips);
         video_files
             .into_iter()
-            .map(|v| (v.clone(), v.overlapping(&audio_files)))
+            .map(|v| {
+                let audios = audio_files.iter().filter(|a| overrides.belongs(&v, a)).cloned().collect();
+                (v, audios)
+            })
             .collect()
     }
 
-    pub fn candidates<T: AsRef<Path>>(directories: &Vec<T>) -> Vec<VideoAudioGroup> {
-        let mut media: Vec<_> = Self::grouped_media(directories)
+    /// videos among `clips` with the audio overlapping them, by start
+    pub fn groups(clips: Vec<Clip>, overrides: &Overrides) -> Vec<VideoAudioGroup> {
+        let mut media: Vec<_> = Self::grouped_media(clips, overrides)
             .into_iter()
             .filter(|(_v, a)| !a.is_empty())
             .collect();
-        media.sort_by(
-            |(one, _), (other, __)|
-                one.start()
-                    .unwrap()
-                    .cmp(&other.start().unwrap())
-        );
+        // a recording forced into the group of a video without a start makes the video a candidate too
+        media.sort_by_key(|(video, _)| video.start());
         media
     }
 }
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+use crate::clip::Clip;
+use crate::ffmpeg_wrapper::Ffmpeg;
+use crate::media_file::MediaFile;
+
+/// claps are short, so a low sample rate is plenty to find them
+const ANALYSIS_SAMPLE_RATE: u32 = 8000;
+/// a window needs to be this much louder than the second before it to count as a clap
+const CLAP_RATIO: f64 = 8.;
+/// and at least this loud, relative to full scale
+const CLAP_MIN_PEAK: f64 = 0.3;
+/// a part whose timecode is further than this from where the one before it ended jumped
+const TIMECODE_JUMP_SECONDS: f64 = 0.1;
+const SECONDS_PER_DAY: f64 = 86400.;
+
+/// point in a file that can be used to line it up with other recordings
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncAnchor {
+    /// seconds from the start of the file
+    pub position: f64,
+    pub label: String,
+}
+
+/// how many 10ms windows before one it is compared to, a second
+const TRANSIENT_HISTORY: usize = 100;
+
+/// finds sudden, loud transients such as slate claps in audio fed to it a piece at a time.
+/// only the peaks of the last second are kept, however long the recording
+pub struct TransientDetector {
+    sample_rate: u32,
+    window: usize,
+    /// peak and number of samples of the window being filled
+    peak: f64,
+    filled: usize,
+    windows: usize,
+    history: VecDeque<f64>,
+    found: Vec<f64>,
+}
+
+impl TransientDetector {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            window: (sample_rate as usize / 100).max(1),
+            peak: 0.,
+            filled: 0,
+            windows: 0,
+            history: VecDeque::with_capacity(TRANSIENT_HISTORY),
+            found: vec![],
+        }
+    }
+
+    pub fn feed(&mut self, samples: &[i16]) {
+        for sample in samples {
+            self.peak = self.peak.max((*sample as f64 / i16::MAX as f64).abs());
+            self.filled += 1;
+            if self.filled == self.window {
+                self.close_window();
+            }
+        }
+    }
+
+    fn close_window(&mut self) {
+        if !self.history.is_empty() {
+            let background = self.history.iter().map(|p| p * p).sum::<f64>() / self.history.len() as f64;
+            let background = background.sqrt();
+            let position = (self.windows * self.window) as f64 / self.sample_rate as f64;
+            let loud = self.peak >= CLAP_MIN_PEAK && self.peak >= background * CLAP_RATIO;
+            // one clap rings for a while, only its attack is interesting
+            let separate = self.found.last().is_none_or(|last| position - last >= 1.);
+            if loud && separate {
+                self.found.push(position);
+            }
+        }
+        if self.history.len() == TRANSIENT_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.peak);
+        self.windows += 1;
+        self.peak = 0.;
+        self.filled = 0;
+    }
+
+    /// positions (in seconds) of the transients in everything fed
+    pub fn finish(mut self) -> Vec<f64> {
+        if self.filled > 0 {
+            self.close_window();
+        }
+        self.found
+    }
+}
+
+/// claps found in the file's audio, labeled with the file name
+pub fn detect_claps(file: &MediaFile) -> Vec<SyncAnchor> {
+    let mut detector = TransientDetector::new(ANALYSIS_SAMPLE_RATE);
+    if Ffmpeg::stream_mono_pcm(file.entry.path(), ANALYSIS_SAMPLE_RATE, |samples| detector.feed(samples)).is_none() {
+        return vec![]
+    }
+    detector
+        .finish()
+        .into_iter()
+        .enumerate()
+        .map(|(index, position)| SyncAnchor {
+            position,
+            label: format!("clap {} {}", index + 1, file.filename()),
+        })
+        .collect()
+}
+
+/// parts of `clip` whose timecode doesn't continue where the part before it ended, eg. after the clock was set mid-take.
+/// the anchor is at the start of the part the jump is in, labeled with how far it jumped
+pub fn timecode_jumps(clip: &Clip) -> Vec<(&MediaFile, SyncAnchor)> {
+    clip.parts
+        .windows(2)
+        .filter_map(|pair| {
+            let (previous, next) = (&pair[0], &pair[1]);
+            let expected = previous.timecode()? + previous.exact_duration()?;
+            // timecode starts over at midnight
+            let jump = (next.timecode()? - expected + SECONDS_PER_DAY / 2.).rem_euclid(SECONDS_PER_DAY) - SECONDS_PER_DAY / 2.;
+            match jump.abs() > TIMECODE_JUMP_SECONDS {
+                true => Some((next, SyncAnchor {
+                    position: 0.,
+                    label: format!("timecode jump {:+.3}s {}", jump, next.filename()),
+                })),
+                false => None,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{self, IsTerminal};
+use std::process::{Command, Stdio};
+
+/// switches to the alternate screen and hides the cursor
+pub const ENTER_SCREEN: &str = "\x1b[?1049h\x1b[?25l";
+pub const LEAVE_SCREEN: &str = "\x1b[?25h\x1b[?1049l";
+/// moves to the top left corner and clears the screen
+pub const CLEAR: &str = "\x1b[H\x1b[2J";
+
+/// a key pressed in raw mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// left and right with shift held
+    ShiftLeft,
+    ShiftRight,
+    Escape,
+    Char(char),
+}
+
+/// the keys in what a read from the terminal returned, arrows arrive as escape sequences (`ESC [ C`, `ESC [ 1 ; 2 C` with shift)
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = vec![];
+    let mut rest = bytes;
+    while let Some((first, after)) = rest.split_first() {
+        rest = after;
+        match first {
+            0x1b if rest.first() == Some(&b'[') => {
+                let end = rest.iter().position(|b| b.is_ascii_alphabetic() || *b == b'~').unwrap_or(rest.len() - 1);
+                let (sequence, after) = rest.split_at(end + 1);
+                rest = after;
+                let shift = sequence.ends_with(b";2C") || sequence.ends_with(b";2D");
+                match (sequence.last(), shift) {
+                    (Some(b'A'), _) => keys.push(Key::Up),
+                    (Some(b'B'), _) => keys.push(Key::Down),
+                    (Some(b'C'), false) => keys.push(Key::Right),
+                    (Some(b'D'), false) => keys.push(Key::Left),
+                    (Some(b'C'), true) => keys.push(Key::ShiftRight),
+                    (Some(b'D'), true) => keys.push(Key::ShiftLeft),
+                    _ => {},
+                }
+            },
+            0x1b => keys.push(Key::Escape),
+            byte if byte.is_ascii() => keys.push(Key::Char(*byte as char)),
+            _ => {},
+        }
+    }
+    keys
+}
+
+/// whether the review can take over the screen, both ends have to be a terminal
+pub fn interactive() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(File::open("/dev/tty").ok()?)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    match output.status.success() {
+        true => Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+        false => None,
+    }
+}
+
+/// (rows, columns) of the terminal, 24x80 when it can't be asked
+pub fn size() -> (usize, usize) {
+    let size = stty(&["size"]).and_then(|size| {
+        let (rows, columns) = size.split_once(' ')?;
+        Some((rows.parse().ok()?, columns.parse().ok()?))
+    });
+    size.unwrap_or((24, 80))
+}
+
+/// keys are read as they're pressed and not echoed while this is kept, the terminal is restored when it's dropped
+pub struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    /// none when the terminal can't be switched, eg. there's no stty
+    pub fn enable() -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        // a read returns what's there after a tenth of a second, so a whole escape sequence comes at once
+        stty(&["raw", "-echo", "min", "1", "time", "1"])?;
+        Some(Self { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        stty(&[&self.saved]);
+    }
+}
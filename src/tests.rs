@@ -1,7 +1,35 @@
 #[cfg(test)]
 mod tests {
     use crate::daws::reaper::*;
-    use crate::media_file::MediaType;
+    use crate::daws::rpp::{RppNode, RppParam};
+    use crate::media_file::{MediaFile, MediaType, name_family, parse_timecode};
+    use crate::hashing::{guid, quick_hash, full_hash};
+    use crate::sync::{timecode_jumps, TransientDetector};
+    use crate::ixml::{ixml_chunk, track_names};
+    use std::collections::HashMap;
+    use crate::daws::mlt::{Mlt, MltProfile, MltClip};
+    use crate::clip::{Clip, sequence_of};
+    use crate::render::{Render, AudioCodec};
+    use crate::mix::{MixSource, MixSettings, MixRule, mix_filter, settings_for, load_mix_profiles};
+    use crate::daw_support::ChannelMapping;
+    use crate::loudness::Loudness;
+    use crate::proxy::{ProxySettings, ProxyCodec, remember_original, mark_proxy_directory, original_of, relink_rpp, relink_mlt};
+    use crate::dedup::{identical_groups, deduplicate, rescanned};
+    use crate::cli::Cli;
+    use crate::daw_support::ProjectFormat;
+    use structopt::StructOpt;
+    use crate::ingest::{render_layout, free_name, copy_verified, DEFAULT_LAYOUT};
+    use std::path::{Path, PathBuf};
+    use crate::manifest::{Manifest, ManifestGroup, ManifestClip, ManifestFile, MANIFEST_VERSION};
+    use crate::overrides::{FileOverride, Overrides, RelativeOffset};
+    use crate::report::{Report, FileRecord, StreamRecord, OutputFormat, REPORT_VERSION};
+    use crate::timeline::{Timeline, Bar, DateRange};
+    use crate::review::{Review, ReviewCommand, player_args, DEFAULT_PLAYER};
+    use crate::terminal::{Key, parse_keys};
+    use crate::watch::{Settling, changed_sessions};
+    use crate::walk::{WalkFilter, matches, IGNORE_FILE};
+    use crate::dates::{DateFilter, parse_moment};
+    use crate::output::{ProjectName, ProjectOutput, Collision, relative_path, backup_path, keep_backup, DEFAULT_NAME};
 
     #[test]
     fn test_test() {
@@ -10,7 +38,7 @@ mod tests {
     #[test]
     fn test_reaper_track() {
         assert_eq!(
-            reaper_track(
+            ReaperTrack::new(
                 String::from("45571899-9E84-5151-B211-B16E459C356D"),
                 String::from("BE FOUND SOON_m1.1"),
                 String::from("BE FOUND SOON_m1.1.wav"),
@@ -18,17 +46,39 @@ mod tests {
                 String::from("206.69117913832199"),
                 String::from("007E36C0-EC22-CB19-1344-D87A1BAB7798"),
                 String::from("88564052-9129-3D60-867F-7840E1CD613E"),
-                String::from("WAVE"),
-            ),
+                MediaType::Audio,
+            ).as_string(),
             String::from(REAPER_TRACK_EXAMPLE),
         );
     }
     #[test]
-    fn test_reaper_project_render() {
+    fn test_reaper_project_round_trip() {
+        let project = RppNode::parse(REAPER_EXAMPLE).unwrap();
+        assert_eq!(project.name, "REAPER_PROJECT");
+        assert_eq!(project.param_value(2), Some("1578133917"));
+        assert_eq!(project.to_string(), REAPER_EXAMPLE);
+    }
+    #[test]
+    fn test_reaper_track_round_trip() {
+        let track = RppNode::parse(REAPER_TRACK_EXAMPLE).unwrap();
+        let source = track.find("ITEM").and_then(|i| i.find("SOURCE")).unwrap();
+        assert_eq!(source.param_value(0), Some("WAVE"));
         assert_eq!(
-            reaper_project_file(vec![String::from(REAPER_TRACK_EXAMPLE)], String::from("1578133917")),
-            String::from(REAPER_EXAMPLE),
+            source.find("FILE").and_then(|f| f.param_value(0)),
+            Some("/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"),
         );
+        assert_eq!(track.to_string(), REAPER_TRACK_EXAMPLE);
+    }
+    #[test]
+    fn test_rpp_parse_quoting() {
+        let node = RppNode::parse("<VST \"VST: ReaEQ (Cockos)\" reaeq.so 0 '' 1919247729<5653>\n  ZXE=\n  `say \"hi\" it's`\n>").unwrap();
+        assert_eq!(node.params[0], RppParam::Quoted(String::from("VST: ReaEQ (Cockos)")));
+        assert_eq!(node.params[3], RppParam::Quoted(String::new()));
+        assert_eq!(node.params[4], RppParam::Bare(String::from("1919247729<5653>")));
+        assert_eq!(node.children[0].name, "ZXE=");
+        assert_eq!(node.children[1].name, "say \"hi\" it's");
+        assert!(RppNode::parse("<TRACK\n").is_err());
+        assert!(RppNode::parse("NAME x").is_err());
     }
     #[test]
     fn test_reaper_project_object() {
@@ -46,4 +96,1442 @@ mod tests {
             String::from("1578133917"),
         ).project_file(), REAPER_EXAMPLE)
     }
+    #[test]
+    fn test_mlt_project() {
+        let project = Mlt::new(
+            MltProfile::new(1920, 1080, 30000, 1001),
+            vec![
+                MltClip::new(String::from("VID_1.mp4"), String::from("/media/VID_1.mp4"), 2., 10., MediaType::Video),
+                MltClip::new(String::from("ZOOM0001.WAV"), String::from("/media/R&D/ZOOM0001.WAV"), 0., 15., MediaType::Audio),
+            ],
+        );
+        assert_eq!(project.project_file(), MLT_EXAMPLE);
+
+        let blip = Mlt::new(
+            MltProfile::new(1920, 1080, 25, 1),
+            vec![MltClip::new(String::from("VID_2.mp4"), String::from("/media/VID_2.mp4"), 0., 0.01, MediaType::Video)],
+        );
+        assert!(!blip.project_file().contains("out=\"-1\""));
+        assert!(blip.project_file().contains("<entry producer=\"producer0\" in=\"0\" out=\"0\"/>"));
+    }
+
+    const MLT_EXAMPLE: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<mlt LC_NUMERIC="C" version="6.18.0" producer="tractor0">
+  <profile description="1920x1080 29.97fps" width="1920" height="1080" progressive="1" sample_aspect_num="1" sample_aspect_den="1" display_aspect_num="16" display_aspect_den="9" frame_rate_num="30000" frame_rate_den="1001" colorspace="709"/>
+  <producer id="producer0" in="0" out="299">
+    <property name="length">300</property>
+    <property name="resource">/media/VID_1.mp4</property>
+    <property name="mlt_service">avformat</property>
+  </producer>
+  <producer id="producer1" in="0" out="449">
+    <property name="length">450</property>
+    <property name="resource">/media/R&amp;D/ZOOM0001.WAV</property>
+    <property name="mlt_service">avformat</property>
+  </producer>
+  <playlist id="playlist0">
+    <blank length="60"/>
+    <entry producer="producer0" in="0" out="299"/>
+  </playlist>
+  <playlist id="playlist1">
+    <entry producer="producer1" in="0" out="449"/>
+  </playlist>
+  <tractor id="tractor0" in="0" out="449">
+    <track producer="playlist0"/>
+    <track producer="playlist1" hide="video"/>
+    <transition id="transition0">
+      <property name="a_track">0</property>
+      <property name="b_track">1</property>
+      <property name="mlt_service">mix</property>
+      <property name="always_active">1</property>
+      <property name="sum">1</property>
+    </transition>
+  </tractor>
+</mlt>
+"#;
+
+    #[test]
+    fn test_reaper_settings() {
+        let track = ReaperTrack::new(
+            String::from("45571899-9E84-5151-B211-B16E459C356D"),
+            String::from("ZOOM0001"),
+            String::from("ZOOM0001.WAV"),
+            String::from("/media/ZOOM0001.WAV"),
+            String::from("125"),
+            String::from("007E36C0-EC22-CB19-1344-D87A1BAB7798"),
+            String::from("88564052-9129-3D60-867F-7840E1CD613E"),
+            MediaType::Audio,
+        ).with_channels(5);
+        let settings = ReaperSettings { sample_rate: Some(48000), frame_rate: Some((30000, 1001)), length: Some(125.) };
+        let project = Reaper::new(vec![track], String::from("1578133917")).with_settings(settings).project();
+        let line = |name: &str| project.find(name).unwrap().params.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(" ");
+        assert_eq!(line("SAMPLERATE"), "48000 1 0");
+        assert_eq!(line("TIMEMODE"), "5 5 -1 29.97 0 0 -1");
+        assert_eq!(line("SMPTESYNC"), "0 29.97 100 40 1000 300 0 0 1 0 0");
+        assert_eq!(line("MAXPROJLEN"), "0 125");
+        let track = project.find("TRACK").unwrap();
+        assert_eq!(track.find("NCHAN").unwrap().param_value(0), Some("6"));
+        let nchan = |channels| ReaperTrack::empty(String::from("A"), String::from("A")).with_channels(channels).as_node().find("NCHAN").unwrap().to_string();
+        assert_eq!(nchan(1), "NCHAN 2\n");
+        assert_eq!(nchan(2), "NCHAN 2\n");
+        assert_eq!(nchan(5), "NCHAN 6\n");
+    }
+    #[test]
+    fn test_reaper_markers() {
+        let project = Reaper::new(vec![], String::from("1578133917"))
+            .with_markers(vec![
+                ReaperMarker::new(12.5, "clap 1 ZOOM0001.WAV"),
+                ReaperMarker::region(2., 32., "VID_1.mp4 (android)"),
+                ReaperMarker::new(2., "start VID_1.mp4"),
+            ])
+            .project();
+        let markers: Vec<String> = project.find_all("MARKER").map(|m| m.to_string()).collect();
+        assert_eq!(markers, vec![
+            "MARKER 1 2 \"VID_1.mp4 (android)\" 1\n",
+            "MARKER 1 32 \"\" 1\n",
+            "MARKER 1 2 \"start VID_1.mp4\" 0\n",
+            "MARKER 2 12.5 \"clap 1 ZOOM0001.WAV\" 0\n",
+        ]);
+    }
+    #[test]
+    fn test_find_transients() {
+        let rate = 8000;
+        let mut samples = vec![100i16; rate * 5];
+        for sample in samples[rate * 2..rate * 2 + 400].iter_mut() {
+            *sample = 30000;
+        }
+        for sample in samples[rate * 4..rate * 4 + 80].iter_mut() {
+            *sample = -25000;
+        }
+        let find_transients = |samples: &[i16]| {
+            let mut detector = TransientDetector::new(rate as u32);
+            detector.feed(samples);
+            detector.finish()
+        };
+        assert_eq!(find_transients(&samples), vec![2., 4.]);
+        assert!(find_transients(&vec![100i16; rate]).is_empty());
+        // fed in pieces that don't line up with the 10ms windows, as ffmpeg's output arrives
+        let mut detector = TransientDetector::new(rate as u32);
+        for piece in samples.chunks(333) {
+            detector.feed(piece);
+        }
+        assert_eq!(detector.finish(), vec![2., 4.]);
+    }
+    #[test]
+    fn test_reaper_folders() {
+        let color = device_color("zoom-h6");
+        assert_eq!(color, device_color("zoom-h6"));
+        assert_ne!(color, device_color("android"));
+        assert_eq!(color & 0xff00_0000, 0x0100_0000);
+        let item = |name: &str| ReaperItem::new(
+            String::from("88564052-9129-3D60-867F-7840E1CD613E"),
+            String::from("007E36C0-EC22-CB19-1344-D87A1BAB7798"),
+            String::from(name),
+            format!("/media/{}", name),
+            String::from("10"),
+            MediaType::Audio,
+        );
+        let project = Reaper::new(
+            vec![
+                ReaperTrack::empty(String::from("A"), String::from("zoom-h6")).with_folder(FolderState::FolderStart).with_color(Some(color)),
+                ReaperTrack::empty(String::from("B"), String::from("ZOOM0001_Tr1.WAV")).with_item(item("ZOOM0001_Tr1.WAV").in_group(Some(1))),
+                ReaperTrack::empty(String::from("C"), String::from("ZOOM0001_Tr2.WAV")).with_item(item("ZOOM0001_Tr2.WAV")).with_folder(FolderState::FolderEnd),
+            ],
+            String::from("1578133917"),
+        );
+        let project = project.project();
+        let tracks: Vec<_> = project.find_all("TRACK").collect();
+        let line = |track: &RppNode, name: &str| track.find(name).unwrap().to_string();
+        assert_eq!(line(tracks[0], "ISBUS"), "ISBUS 1 1\n");
+        assert_eq!(line(tracks[0], "PEAKCOL"), format!("PEAKCOL {}\n", color));
+        assert!(tracks[0].find("ITEM").is_none());
+        assert_eq!(line(tracks[1], "ISBUS"), "ISBUS 0 0\n");
+        assert_eq!(line(tracks[1].find("ITEM").unwrap(), "GROUP"), "GROUP 1\n");
+        assert_eq!(line(tracks[2], "ISBUS"), "ISBUS 2 -1\n");
+        assert!(tracks[2].find("ITEM").unwrap().find("GROUP").is_none());
+    }
+    #[test]
+    fn test_reaper_merge_without_known_media() {
+        let mut project = RppNode::parse(REAPER_EXAMPLE).unwrap();
+        assert!(merge_into(&mut project, &[], Path::new(".")).is_empty());
+        assert_eq!(project.to_string(), REAPER_EXAMPLE);
+    }
+    /// a media file at `path` (written if missing) with the ffprobe output of a `duration` seconds recording carrying `tags`
+    fn probed(path: &Path, duration: f64, tags: &str) -> MediaFile {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        if !path.exists() {
+            std::fs::write(path, path.display().to_string()).unwrap();
+        }
+        let stream = match path.extension().and_then(|e| e.to_str()) {
+            Some("WAV") | Some("wav") => format!(
+                r#"{{"index": 0, "codec_type": "audio", "sample_rate": "48000", "channels": 2, "time_base": "1/48000", "duration_ts": {}, "duration": "{}", "disposition": {{}}}}"#,
+                (duration * 48000.) as u64, duration,
+            ),
+            _ => format!(
+                r#"{{"index": 0, "codec_type": "video", "width": 1920, "height": 1080, "r_frame_rate": "25/1", "duration_ts": {}, "duration": "{}", "disposition": {{}}}}"#,
+                (duration * 25.) as u64, duration,
+            ),
+        };
+        let json = format!(r#"{{"streams": [{}], "format": {{"tags": {}}}}}"#, stream, tags);
+        let entry = walkdir::WalkDir::new(path).into_iter().next().unwrap().unwrap();
+        MediaFile::from_probe(entry, serde_json::from_str(&json).unwrap()).unwrap()
+    }
+
+    /// a phone video ending at `end` (eg. `10:16:00`) on 2020-01-04
+    fn phone_video(path: &Path, duration: f64, end: &str) -> MediaFile {
+        // android files are an hour behind in their metadata
+        let end = chrono::NaiveDateTime::parse_from_str(&format!("2020-01-04 {}", end), "%F %T").unwrap() - chrono::Duration::hours(1);
+        probed(path, duration, &format!(r#"{{"creation_time": "{}"}}"#, end.format("%FT%T%.6fZ")))
+    }
+
+    /// a zoom recording ending at `end` on 2020-01-04
+    fn zoom_audio(path: &Path, duration: f64, end: &str) -> MediaFile {
+        probed(path, duration, &format!(r#"{{"date": "2020-01-04", "creation_time": "{}"}}"#, end))
+    }
+
+    #[test]
+    fn test_reaper_merge_overlapping_audio() {
+        let dir = std::env::temp_dir().join("smergy-test-merge");
+        let _ = std::fs::remove_dir_all(&dir);
+        // scanned by a path relative to the current directory, like `-d footage`
+        let up: PathBuf = std::env::current_dir().unwrap().components().skip(1).map(|_| "..").collect();
+        let scanned = up.join(dir.strip_prefix("/").unwrap());
+        let video = phone_video(&scanned.join("VID_1.mp4"), 60., "10:16:00");
+        let known_audio = dir.join("card1/ZOOM0001.WAV");
+        zoom_audio(&known_audio, 30., "10:15:30");
+        // same name on another card, a different recording that starts 5s before the video
+        let new_audio = zoom_audio(&scanned.join("card2/ZOOM0001.WAV"), 30., "10:15:25");
+        let source = |path: &Path, kind: &str, length: u32| format!(
+            "  <TRACK\n    <ITEM\n      POSITION 0\n      LENGTH {}\n      SOFFS 0\n      <SOURCE {}\n        FILE \"{}\"\n      >\n    >\n  >\n",
+            length, kind, path.display(),
+        );
+        // the project is in a directory of its own and refers to the video relative to it
+        let mut project = RppNode::parse(&format!(
+            "<REAPER_PROJECT 0.1 \"6.0\" 1578133917\n{}{}>\n",
+            source(Path::new("../VID_1.mp4"), "VIDEO", 60),
+            source(&known_audio, "WAVE", 30),
+        )).unwrap();
+        let projects = dir.join("projects");
+        std::fs::create_dir_all(&projects).unwrap();
+
+        assert_eq!(merge_into(&mut project, &[video.clone(), new_audio.clone()], &projects), vec![String::from("ZOOM0001.WAV")]);
+        let tracks: Vec<_> = project.find_all("TRACK").collect();
+        assert_eq!(tracks.len(), 3);
+        let item = tracks[2].find("ITEM").unwrap();
+        assert_eq!(item.find("SOURCE").unwrap().find("FILE").unwrap().param_value(0), Some("../card2/ZOOM0001.WAV"));
+        assert_eq!(item.find("POSITION").unwrap().param_value(0), Some("0"));
+        assert_eq!(item.find("SOFFS").unwrap().param_value(0), Some("5"));
+        assert_eq!(item.find("LENGTH").unwrap().param_value(0), Some("25"));
+        let exported = ReaperTrack::from_media_file(&new_audio, &video.content_hash().unwrap()).item_nodes();
+        assert_eq!(item.find("IGUID"), exported[0].find("IGUID"));
+        assert!(merge_into(&mut project, &[video, new_audio], &projects).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_reaper_name_family() {
+        assert_eq!(name_family("ZOOM0001_LR.WAV"), name_family("ZOOM0042_LR.WAV"));
+        assert_ne!(name_family("ZOOM0001_LR.WAV"), name_family("ZOOM0001_Tr1.WAV"));
+        assert_ne!(name_family("ZOOM0001_Tr1.WAV"), name_family("ZOOM0002_Tr2.WAV"));
+        assert_eq!(name_family("ZOOM0001_Tr1.WAV"), name_family("ZOOM0002_Tr1.WAV"));
+        assert_eq!(name_family("VID_20200104_101500.mp4"), name_family("VID_20200104_103012.mp4"));
+    }
+
+    #[test]
+    fn test_clip_sequences() {
+        assert_eq!(sequence_of("GOPR1234.MP4"), (String::from("gopro 1234 MP4"), 0));
+        assert_eq!(sequence_of("GP011234.MP4"), (String::from("gopro 1234 MP4"), 1));
+        assert_eq!(sequence_of("GP021234.MP4").1, 2);
+        assert_eq!(sequence_of("GX011234.MP4"), (String::from("gopro GX 1234 MP4"), 0));
+        assert_eq!(sequence_of("GX021234.MP4"), (String::from("gopro GX 1234 MP4"), 1));
+        assert_ne!(sequence_of("GX011234.MP4").0, sequence_of("GX011235.MP4").0);
+        assert_eq!(sequence_of("ZOOM0001_LR.WAV"), (String::from("ZOOM0001_LR WAV"), 0));
+        assert_eq!(sequence_of("ZOOM0001_LR-0001.wav"), (String::from("ZOOM0001_LR WAV"), 1));
+        assert_eq!(sequence_of("VID_20200101_120000.mp4").1, 0);
+    }
+
+    #[test]
+    fn test_render_alignment() {
+        let late = vec![MixSource::new(vec![PathBuf::from("a.wav")], 1.5)];
+        assert_eq!(mix_filter(&late, 1, 60.), "[1:a]concat=n=1:v=0:a=1,adelay=1500:all=1,apad,atrim=end=60.000000[external]");
+
+        let early = vec![MixSource::new(vec![PathBuf::from("a.wav"), PathBuf::from("a-0001.wav")], -2.25)];
+        assert_eq!(
+            mix_filter(&early, 1, 60.),
+            "[1:a][2:a]concat=n=2:v=0:a=1,atrim=start=2.250000,asetpts=PTS-STARTPTS,apad,atrim=end=60.000000[external]",
+        );
+        // a recorder whose clock runs 20ppm fast plays that much faster, like in the reaper export
+        let drifting = vec![MixSource::new(vec![PathBuf::from("a.wav")], 0.).with_drift(20.)];
+        assert_eq!(mix_filter(&drifting, 1, 60.), "[1:a]concat=n=1:v=0:a=1,atempo=1.000020000,apad,atrim=end=60.000000[external]");
+    }
+
+    #[test]
+    fn test_render_mixdown() {
+        let boom: MixSettings = "channels=3,gain=-3,pan=-0.5".parse().unwrap();
+        assert_eq!(boom, MixSettings { gain_db: -3., pan: -0.5, channels: ChannelMapping::Mono(3), mute: false });
+        let sources = vec![
+            MixSource::new(vec![PathBuf::from("lav.wav")], 0.),
+            MixSource::new(vec![PathBuf::from("scratch.wav")], 0.).with_settings("mute".parse().unwrap()),
+            MixSource::new(vec![PathBuf::from("h6.wav")], 2.).with_settings(boom),
+        ];
+        assert_eq!(
+            mix_filter(&sources, 1, 10.),
+            "[1:a]concat=n=1:v=0:a=1[s0];\
+             [3:a]concat=n=1:v=0:a=1,pan=stereo|c0=1*c2|c1=0.5*c2,adelay=2000:all=1,volume=-3dB[s1];\
+             [s0][s1]amix=inputs=2:duration=longest,apad,atrim=end=10.000000[external]",
+        );
+
+        let rules: Vec<MixRule> = vec!["zoom-h6:gain=-6".parse().unwrap(), "ZOOM0001_TR1.WAV:mute".parse().unwrap()];
+        assert!(settings_for(&rules, "zoom0001_tr1.wav", "zoom-h6", MixSettings::default()).mute);
+        assert_eq!(settings_for(&rules, "ZOOM0001_TR2.WAV", "zoom-h6", MixSettings::default()).gain_db, -6.);
+        assert_eq!(settings_for(&rules, "VID.MP4", "android", MixSettings::default()), MixSettings::default());
+        assert!("channels=2-4".parse::<MixSettings>().is_err());
+
+        let dir = std::env::temp_dir().join("smergy-test-mix-profiles");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let profiles = dir.join("mix-profiles");
+        std::fs::write(&profiles, "# boom on the left\nzoom-h6:gain=-3,pan=-0.5\n\n").unwrap();
+        let rules = [load_mix_profiles(&profiles).unwrap(), vec!["zoom-h6:gain=2".parse().unwrap()]].concat();
+        assert_eq!(settings_for(&rules[..1], "ZOOM0001_TR1.WAV", "zoom-h6", MixSettings::default()).pan, -0.5);
+        assert_eq!(settings_for(&rules, "ZOOM0001_TR1.WAV", "zoom-h6", MixSettings::default()).gain_db, 2.);
+        std::fs::write(&profiles, "ZOOM0001.WAV:mute\n").unwrap();
+        assert_eq!(
+            load_mix_profiles(&profiles),
+            Err(format!("{}:1: there's no device profile called ZOOM0001.WAV", profiles.display())),
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_args() {
+        let sources = vec![MixSource::new(vec![PathBuf::from("a.wav")], 0.)];
+        let render = Render::new(vec![PathBuf::from("v.mp4")], sources, 10., PathBuf::from("v_synced.mp4"))
+            .with_codec(AudioCodec::Pcm)
+            .keeping_camera_audio(true);
+        let args = render.args(Path::new("v.mp4"), &render.filter()).join(" ");
+        assert!(args.starts_with("-y -i v.mp4 -i a.wav -filter_complex"));
+        assert!(args.contains("-map 0:v:0 -c:v copy -map [external] -c:a:0 pcm_s24le"));
+        assert!(args.contains("-map 0:a:0 -c:a:1 copy"));
+        assert!(args.ends_with("-t 10.000000 v_synced.mov"));
+        // a camera that recorded no audio has none to keep
+        let render = render.with_camera_audio(false);
+        assert!(!render.args(Path::new("v.mp4"), &render.filter()).join(" ").contains("-map 0:a"));
+        assert_eq!("flac".parse::<AudioCodec>(), Ok(AudioCodec::Flac));
+
+        // the list is read from the temp directory, relative parts have to be written absolute
+        let parts = vec![PathBuf::from("src/main.rs"), PathBuf::from("src/clip.rs")];
+        let render = Render::new(parts, vec![], 10., PathBuf::from("/projects/v_synced.mp4"));
+        let absolute = |path: &str| std::fs::canonicalize(path).unwrap().display().to_string();
+        assert_eq!(render.concat_list(), format!("file '{}'\nfile '{}'\n", absolute("src/main.rs"), absolute("src/clip.rs")));
+    }
+
+    #[test]
+    fn test_loudness() {
+        let output = r#"[Parsed_loudnorm_0 @ 0x55d5c8b0a3c0]
+{
+	"input_i" : "-27.61",
+	"input_tp" : "-4.47",
+	"input_lra" : "6.20",
+	"input_thresh" : "-38.01",
+	"output_i" : "-16.58",
+	"output_tp" : "-1.50",
+	"output_lra" : "5.10",
+	"output_thresh" : "-27.08",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.58"
+}"#;
+        let loudness = Loudness::from_loudnorm_output(output).unwrap();
+        assert_eq!(loudness, Loudness { integrated: -27.61, true_peak: -4.47, range: 6.2, threshold: -38.01 });
+        assert!((loudness.gain_to(-16.) - 11.61).abs() < 1e-9);
+        assert_eq!(
+            loudness.loudnorm_filter(-23.),
+            "loudnorm=I=-23:TP=-1:LRA=7:measured_I=-27.61:measured_TP=-4.47:measured_LRA=6.2:measured_thresh=-38.01:linear=true",
+        );
+        assert_eq!(Loudness::from_loudnorm_output("{\"input_i\" : \"-inf\"}"), None);
+        assert_eq!(Loudness::from_loudnorm_output("} and then {\"input_i\""), None);
+
+        let combined = Loudness::combine(&[(loudness, 10.), (Loudness { integrated: -20., ..loudness }, 30.)]).unwrap();
+        assert!(combined.integrated > -21.3 && combined.integrated < -21.);
+        assert_eq!(combined.true_peak, -4.47);
+    }
+
+    #[test]
+    fn test_reaper_item_gain() {
+        let item = ReaperItem::new(
+            String::from("ITEM"),
+            String::from("TAKE"),
+            String::from("a.wav"),
+            String::from("/a.wav"),
+            String::from("1"),
+            MediaType::Audio,
+        );
+        let track = ReaperTrack::empty(String::from("TRACK"), String::from("a")).with_item(item.with_gain(-6.0206)).as_node();
+        let volume: f64 = track.find("ITEM").unwrap().find("VOLPAN").unwrap().param_value(0).unwrap().parse().unwrap();
+        assert!((volume - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_guid() {
+        let id = guid(&["session", "content", "track"]);
+        assert_eq!(id, guid(&["session", "content", "track"]));
+        assert_ne!(id, guid(&["session", "content", "item"]));
+        assert_ne!(guid(&["ab", "c"]), guid(&["a", "bc"]));
+        assert_eq!(id.len(), 36);
+        assert_eq!(id.split('-').map(|p| p.len()).collect::<Vec<_>>(), vec![8, 4, 4, 4, 12]);
+        assert_eq!(id, id.to_uppercase());
+    }
+    #[test]
+    fn test_quick_hash() {
+        let dir = std::env::temp_dir().join("smergy-test-quick-hash");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.wav"), vec![1u8; 3 * 1024 * 1024]).unwrap();
+        std::fs::write(dir.join("b.wav"), vec![1u8; 3 * 1024 * 1024]).unwrap();
+        let mut different = vec![1u8; 3 * 1024 * 1024];
+        different[3 * 1024 * 1024 - 1] = 2;
+        std::fs::write(dir.join("c.wav"), different).unwrap();
+        assert_eq!(quick_hash(dir.join("a.wav")), quick_hash(dir.join("b.wav")));
+        assert_ne!(quick_hash(dir.join("a.wav")), quick_hash(dir.join("c.wav")));
+        assert_eq!(quick_hash(dir.join("missing.wav")), None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_proxy_naming() {
+        let settings = ProxySettings::new(ProxyCodec::Mjpeg);
+        assert_eq!(settings.proxy_path(Path::new("/footage/VID_0001.mp4")), PathBuf::from("/footage/proxies/VID_0001_proxy.mov"));
+        let settings = ProxySettings::new(ProxyCodec::H264Intra)
+            .in_directory(Some(PathBuf::from("/cache")))
+            .with_suffix(".low");
+        assert_eq!(settings.proxy_path(Path::new("/footage/VID_0001.mp4")), PathBuf::from("/cache/VID_0001.low.mp4"));
+        let args = settings.args(Path::new("in.mp4"), Path::new("out.mp4")).join(" ");
+        assert!(args.starts_with("-y -i in.mp4 -vf scale=-2:540 -c:v libx264"));
+        assert!(args.contains("-g 1 -bf 0"));
+    }
+
+    #[test]
+    fn test_proxy_relink() {
+        let dir = std::env::temp_dir().join("smergy-test-proxy-relink");
+        std::fs::create_dir_all(&dir).unwrap();
+        let proxy = dir.join("VID_0001_proxy.mov");
+        remember_original(&proxy, Path::new("/footage/VID & 1.mp4")).unwrap();
+
+        let mut project = RppNode::chunk("REAPER_PROJECT").child(
+            RppNode::chunk("TRACK").child(
+                RppNode::chunk("ITEM").child(
+                    RppNode::chunk("SOURCE").param("VIDEO").child(RppNode::line("FILE").quoted(proxy.display()))
+                )
+            )
+        );
+        assert_eq!(relink_rpp(&mut project, &dir), 1);
+        assert!(project.to_string().contains("FILE \"/footage/VID & 1.mp4\""));
+        assert_eq!(relink_rpp(&mut project, &dir), 0);
+
+        let mlt = format!("<mlt>\n    <property name=\"resource\">{}</property>\n</mlt>\n", proxy.display());
+        let (relinked, count) = relink_mlt(&mlt, &dir);
+        assert_eq!(count, 1);
+        assert_eq!(relinked, "<mlt>\n    <property name=\"resource\">/footage/VID &amp; 1.mp4</property>\n</mlt>\n");
+
+        // a project referring to the proxy relative to itself gets the original relative to itself
+        std::fs::create_dir_all(dir.join("footage/proxies")).unwrap();
+        std::fs::create_dir_all(dir.join("projects")).unwrap();
+        std::fs::write(dir.join("footage/VID_1.mp4"), "video").unwrap();
+        remember_original(&dir.join("footage/proxies/VID_1_proxy.mov"), &dir.join("footage/VID_1.mp4")).unwrap();
+        let mut project = RppNode::line("FILE").quoted("../footage/proxies/VID_1_proxy.mov");
+        assert_eq!(relink_rpp(&mut project, &dir.join("projects")), 1);
+        assert_eq!(project.param_value(0), Some("../footage/VID_1.mp4"));
+        let mlt = "<property name=\"resource\">../footage/proxies/VID_1_proxy.mov</property>";
+        assert_eq!(relink_mlt(mlt, &dir.join("projects")).0, "<property name=\"resource\">../footage/VID_1.mp4</property>");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ingest_layout() {
+        assert_eq!(
+            render_layout(DEFAULT_LAYOUT, "2020-01-04", "zoom-h6", "VID_20200104_101500"),
+            PathBuf::from("2020-01-04/zoom-h6/VID_20200104_101500"),
+        );
+        assert_eq!(render_layout("{session}-{device}", "d", "a/b", "s"), PathBuf::from("s-a_b"));
+    }
+
+    #[test]
+    fn test_ingest_resumed_copy() {
+        let dir = std::env::temp_dir().join("smergy-test-ingest");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("card1")).unwrap();
+        std::fs::create_dir_all(dir.join("out")).unwrap();
+        let content: Vec<u8> = (0..10 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        let source = dir.join("card1/ZOOM0001.WAV");
+        std::fs::write(&source, &content).unwrap();
+        // an interrupted earlier copy
+        std::fs::write(dir.join("out/ZOOM0001.WAV.part"), &content[..3 * 1024 * 1024]).unwrap();
+
+        let target = free_name(&dir.join("out"), &source).unwrap();
+        assert_eq!(target, dir.join("out/ZOOM0001.WAV"));
+        copy_verified(&source, &target, false).unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), content);
+        assert!(!dir.join("out/ZOOM0001.WAV.part").exists());
+        assert_eq!(full_hash(&source), full_hash(&target));
+
+        // the same recording is reused, a different one with the same name gets a suffix
+        assert_eq!(free_name(&dir.join("out"), &source).unwrap(), target);
+        let other = dir.join("card1/other/ZOOM0001.WAV");
+        std::fs::create_dir_all(other.parent().unwrap()).unwrap();
+        std::fs::write(&other, b"another take").unwrap();
+        assert_eq!(free_name(&dir.join("out"), &other).unwrap(), dir.join("out/ZOOM0001-2.WAV"));
+
+        // a copy that only differs in the middle passes the quick hash, but isn't the same recording
+        let mut damaged = content.clone();
+        damaged[5 * 1024 * 1024] ^= 1;
+        let damaged_source = dir.join("card1/damaged/ZOOM0001.WAV");
+        std::fs::create_dir_all(damaged_source.parent().unwrap()).unwrap();
+        std::fs::write(&damaged_source, &damaged).unwrap();
+        assert_eq!(quick_hash(&damaged_source), quick_hash(&target));
+        assert_eq!(free_name(&dir.join("out"), &damaged_source).unwrap(), dir.join("out/ZOOM0001-2.WAV"));
+
+        // a partial file that doesn't match the source fails verification and is thrown away
+        std::fs::write(dir.join("out/ZOOM0001-2.WAV.part"), b"garbage").unwrap();
+        assert!(copy_verified(&other, &dir.join("out/ZOOM0001-2.WAV"), false).is_err());
+        assert!(!dir.join("out/ZOOM0001-2.WAV.part").exists());
+        copy_verified(&other, &dir.join("out/ZOOM0001-2.WAV"), false).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_identical_groups() {
+        let dir = std::env::temp_dir().join("smergy-test-dedup");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let take: Vec<u8> = (0..5 * 1024 * 1024).map(|i| (i % 253) as u8).collect();
+        // same size, same head and tail, different in the middle: only the full hash tells them apart
+        let mut other_take = take.clone();
+        other_take[2 * 1024 * 1024 + 17] ^= 1;
+        let paths = vec![dir.join("a.wav"), dir.join("b.wav"), dir.join("backup-a.wav"), dir.join("c.wav"), dir.join("backup-b.wav")];
+        std::fs::write(&paths[0], &take).unwrap();
+        std::fs::write(&paths[1], &other_take).unwrap();
+        std::fs::write(&paths[2], &take).unwrap();
+        std::fs::write(&paths[3], b"short").unwrap();
+        std::fs::write(&paths[4], &other_take).unwrap();
+        assert_eq!(quick_hash(&paths[0]), quick_hash(&paths[1]));
+        assert_eq!(identical_groups(&paths), vec![vec![0, 2], vec![1, 4]]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_subcommands() {
+        match Cli::from_iter_safe(&["smergy", "export", "-d", "/media", "-f", "mlt", "--normalize", "-23"]).unwrap() {
+            Cli::Export(args) => {
+                assert_eq!(args.source.directories, vec![PathBuf::from("/media")]);
+                assert_eq!(args.format, ProjectFormat::Mlt);
+                assert_eq!(args.normalize, Some(-23.));
+                assert_eq!(args.ingest.ingest_to, None);
+            },
+            other => panic!("expected export, got {:?}", other),
+        }
+        match Cli::from_iter_safe(&["smergy", "inspect", "VID_1.mp4"]).unwrap() {
+            Cli::Inspect(args) => assert_eq!(args.file, PathBuf::from("VID_1.mp4")),
+            other => panic!("expected inspect, got {:?}", other),
+        }
+        assert!(Cli::from_iter_safe(&["smergy", "scan"]).is_err());
+        assert!(Cli::from_iter_safe(&["smergy", "-d", "/media"]).is_err());
+    }
+
+    #[test]
+    fn test_manifest() {
+        let dir = std::env::temp_dir().join("smergy-test-manifest");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |path: &str| ManifestFile {
+            path: String::from(path),
+            hash: Some(String::from("4a3c")),
+            duration: Some(62.5),
+            duplicates: vec![],
+        };
+        let manifest = Manifest {
+            version: MANIFEST_VERSION,
+            groups: vec![ManifestGroup {
+                session: String::from("2020-01-05_1432"),
+                start: Some(String::from("2020-01-05 14:32:10.000000")),
+                video: ManifestClip {
+                    files: vec![file("/missing/VID_1.mp4")],
+                    device: Some(String::from("gopro")),
+                    timing_source: String::from("gopro metadata"),
+                    offset: Some(0.),
+                    drift_ppm: 0.,
+                },
+                audio: vec![ManifestClip {
+                    files: vec![file("/missing/ZOOM0001.WAV")],
+                    device: Some(String::from("zoom-h6")),
+                    timing_source: String::from("manual"),
+                    offset: Some(-1.25),
+                    drift_ppm: 12.,
+                }],
+                approved: None,
+            }],
+        };
+        let path = dir.join("manifest.json");
+        manifest.save(&path).unwrap();
+        assert_eq!(Manifest::load(&path).unwrap(), manifest);
+        // files that can't be found anymore
+        assert!(manifest.groups().is_err());
+
+        let mut newer = manifest.clone();
+        newer.version = MANIFEST_VERSION + 1;
+        newer.save(&path).unwrap();
+        assert!(Manifest::load(&path).unwrap_err().to_string().contains("newer"));
+
+        // timing survives the round trip, only starts more than a millisecond off count as corrected by hand
+        let video = phone_video(&dir.join("VID_1.mp4"), 60., "10:16:00");
+        let audio = Clip::single(zoom_audio(&dir.join("ZOOM0001.WAV"), 30., "10:15:40"));
+        let saved = ManifestClip::from_clip(&audio, video.start());
+        let reloaded = saved.timed(audio.parts.clone(), video.start());
+        assert_eq!(reloaded.start(), audio.start());
+        assert_eq!(reloaded.timing_source(), "zoom-h6 metadata");
+        let barely = ManifestClip { offset: saved.offset.map(|o| o + 0.0004), ..saved.clone() };
+        assert_eq!(barely.timed(audio.parts.clone(), video.start()).timing_source(), "zoom-h6 metadata");
+        let nudged = ManifestClip { offset: saved.offset.map(|o| o + 0.5), ..saved.clone() };
+        let corrected = nudged.timed(audio.parts.clone(), video.start());
+        assert_eq!(corrected.start(), audio.start().map(|s| s + chrono::Duration::milliseconds(500)));
+        assert_eq!(corrected.timing_source(), "manual");
+
+        // another recording put where the saved one was isn't taken for it
+        let saved_file = ManifestFile::from_media_file(&audio.parts[0]);
+        assert!(saved_file.is(&audio.parts[0]));
+        std::fs::write(dir.join("ZOOM0001.WAV"), "another take").unwrap();
+        assert!(!saved_file.is(&zoom_audio(&dir.join("ZOOM0001.WAV"), 30., "10:15:40")));
+        // saved absolute when scanned relative to the current directory, read relative to the manifest when written so
+        let up: PathBuf = std::env::current_dir().unwrap().components().skip(1).map(|_| "..").collect();
+        let scanned = zoom_audio(&up.join(dir.strip_prefix("/").unwrap()).join("ZOOM0001.WAV"), 30., "10:15:40");
+        assert_eq!(ManifestFile::from_media_file(&scanned).path, dir.join("ZOOM0001.WAV").display().to_string());
+        let mut relative = manifest.clone();
+        relative.groups[0].audio[0].files[0].path = String::from("ZOOM0001.WAV");
+        relative.groups[0].audio[0].files[0].duplicates = vec![String::from("/backup/ZOOM0001.WAV")];
+        relative.save(&path).unwrap();
+        let loaded = Manifest::load(&path).unwrap();
+        assert_eq!(loaded.groups[0].audio[0].files[0].path, dir.join("ZOOM0001.WAV").display().to_string());
+        assert_eq!(loaded.groups[0].audio[0].files[0].duplicates, vec![String::from("/backup/ZOOM0001.WAV")]);
+
+        let bad_start = ManifestGroup { start: Some(String::from("yesterday")), ..manifest.groups[0].clone() };
+        let error = Manifest { version: MANIFEST_VERSION, groups: vec![bad_start] }.groups().unwrap_err().to_string();
+        assert!(error.starts_with("manifest: session 2020-01-05_1432 has a bad start yesterday"));
+
+        assert!(Cli::from_iter_safe(&["smergy", "plan", "-m", "manifest.json"]).is_ok());
+        assert!(Cli::from_iter_safe(&["smergy", "plan", "-m", "manifest.json", "-d", "/media"]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_overrides_file() {
+        let dir = std::env::temp_dir().join("smergy-test-overrides");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("overrides.json");
+        std::fs::write(&path, r#"{
+            "files": {
+                "9f2c": {"note": "phone with a reset clock", "offset": {"from": "4a3c", "seconds": -2.5}, "device": "android"},
+                "77e1": {"start": "2020-01-05 14:32:10.5", "not_in": ["4a3c"]},
+                "0b1d": {"exclude": true}
+            }
+        }"#).unwrap();
+        let overrides = Overrides::load(&path).unwrap();
+        assert_eq!(overrides.files["9f2c"].offset, Some(RelativeOffset { from: String::from("4a3c"), seconds: -2.5 }));
+        assert_eq!(overrides.files["77e1"].not_in, vec![String::from("4a3c")]);
+        assert!(overrides.files["0b1d"].exclude);
+        assert_eq!(overrides.files["0b1d"].group, None);
+
+        std::fs::write(&path, r#"{"files": {"9f2c": {"device": "walkman"}}}"#).unwrap();
+        assert!(Overrides::load(&path).unwrap_err().to_string().contains("walkman"));
+        std::fs::write(&path, r#"{"files": {"9f2c": {"start": "yesterday"}}}"#).unwrap();
+        assert!(Overrides::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_overrides_applied() {
+        let dir = std::env::temp_dir().join("smergy-test-overrides-applied");
+        let _ = std::fs::remove_dir_all(&dir);
+        let video = phone_video(&dir.join("VID_1.mp4"), 60., "10:16:00");
+        let pinned = zoom_audio(&dir.join("ZOOM0001.WAV"), 30., "10:15:40");
+        // a recorder whose clock was reset, started 2s after the pinned one
+        let reset = zoom_audio(&dir.join("ZOOM0002.WAV"), 20., "08:00:20");
+        let elsewhere = zoom_audio(&dir.join("ZOOM0003.WAV"), 30., "10:15:30");
+        let grouped = zoom_audio(&dir.join("ZOOM0004.WAV"), 30., "12:00:00");
+        let excluded = zoom_audio(&dir.join("ZOOM0005.WAV"), 30., "10:15:30");
+        let hash = |file: &MediaFile| file.content_hash().unwrap();
+        let mut overrides = Overrides::default();
+        overrides.files.insert(hash(&pinned), FileOverride { start: Some(String::from("2020-01-04 10:15:05")), ..Default::default() });
+        overrides.files.insert(hash(&reset), FileOverride {
+            offset: Some(RelativeOffset { from: hash(&pinned), seconds: 2. }),
+            ..Default::default()
+        });
+        overrides.files.insert(hash(&elsewhere), FileOverride { not_in: vec![hash(&video)], ..Default::default() });
+        overrides.files.insert(hash(&grouped), FileOverride { group: Some(hash(&video)), ..Default::default() });
+        overrides.files.insert(hash(&excluded), FileOverride { exclude: true, ..Default::default() });
+
+        let files = overrides.without_excluded(vec![pinned, reset, elsewhere, grouped, excluded]);
+        assert_eq!(files.len(), 4);
+        let audios = overrides.apply(files.into_iter().map(Clip::single).collect());
+        let at = |time: &str| chrono::NaiveDateTime::parse_from_str(&format!("2020-01-04 {}", time), "%F %T").ok();
+        assert_eq!(audios[0].start(), at("10:15:05"));
+        assert_eq!(audios[1].start(), at("10:15:07"));
+        let video = Clip::single(video);
+        let belongs: Vec<bool> = audios.iter().map(|a| overrides.belongs(&video, a)).collect();
+        assert_eq!(belongs, vec![true, true, false, true]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_report_output() {
+        let stream = StreamRecord {
+            index: 0,
+            stream_type: Some(String::from("audio")),
+            codec: Some(String::from("pcm_s24le")),
+            channels: Some(2),
+            sample_rate: Some(48000),
+            width: None,
+            height: None,
+            frame_rate: None,
+        };
+        let report = Report {
+            version: REPORT_VERSION,
+            files: vec![FileRecord {
+                group: Some(String::from("4a3c")),
+                path: String::from("/media/day 1, take 2/ZOOM0001.WAV"),
+                media_type: Some(String::from("audio")),
+                duration: Some(62.5),
+                start: Some(String::from("2020-01-05T14:32:10.000000")),
+                end: Some(String::from("2020-01-05T14:33:12.500000")),
+                device: Some(String::from("zoom-h6")),
+                timing_source: String::from("zoom-h6 metadata"),
+                streams: vec![stream],
+            }],
+        };
+        assert_eq!(
+            report.to_csv(),
+            "group,path,type,duration,start,end,device,timing_source,streams\n\
+            4a3c,\"/media/day 1, take 2/ZOOM0001.WAV\",audio,62.500000,2020-01-05T14:32:10.000000,\
+            2020-01-05T14:33:12.500000,zoom-h6,zoom-h6 metadata,pcm_s24le 2ch 48000Hz\n",
+        );
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["files"][0]["type"], "audio");
+        assert_eq!(json["files"][0]["streams"][0]["sample_rate"], 48000);
+        assert_eq!("CSV".parse::<OutputFormat>(), Ok(OutputFormat::Csv));
+        assert!("xml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_timeline() {
+        let at = |minute: u32| chrono::NaiveDate::from_ymd(2020, 1, 5).and_hms(14, minute, 0);
+        let bar = |row: &str, start: u32, end: u32, video: bool, grouped: bool| Bar {
+            row: String::from(row),
+            start: at(start),
+            end: at(end),
+            video,
+            grouped,
+        };
+        let timeline = Timeline::new(vec![
+            bar("android", 0, 10, true, true),
+            bar("zoom-h6", 2, 6, false, true),
+            bar("zoom-h6", 5, 8, false, true),
+            bar("zoom-h6", 12, 20, false, false),
+        ]).unwrap().with_width(20);
+        let lines: Vec<String> = timeline.render().lines().map(String::from).collect();
+        assert_eq!(lines[0], "         2020-01-05 14:00:00 2020-01-05 14:20:00");
+        assert_eq!(lines[1], "android  ██████████··········");
+        assert_eq!(lines[2], "zoom-h6  ··███▓██····░░░░░░░░");
+        assert_eq!(lines[3], "   sync  !!======!!  !!!!!!!!");
+
+        let zoomed = timeline.zoomed(&"2020-01-05 14:10..2020-01-05 14:20".parse::<DateRange>().unwrap()).with_width(10);
+        assert_eq!(zoomed.render().lines().nth(2), Some("zoom-h6  ··░░░░░░░░"));
+
+        let day: DateRange = "2020-01-05".parse().unwrap();
+        assert_eq!((day.from, day.to), (at(0) - chrono::Duration::hours(14), at(0) + chrono::Duration::hours(10)));
+        assert!("2020-01-05 16:00..2020-01-05 14:00".parse::<DateRange>().is_err());
+    }
+
+    #[test]
+    fn test_review_commands() {
+        assert_eq!("t 2".parse::<ReviewCommand>(), Ok(ReviewCommand::Toggle(2)));
+        assert_eq!("nudge 0 -0.25".parse::<ReviewCommand>(), Ok(ReviewCommand::Nudge(0, -0.25)));
+        assert_eq!("p v 12.5".parse::<ReviewCommand>(), Ok(ReviewCommand::Play(None, 12.5)));
+        assert_eq!("  p 1 3 ".parse::<ReviewCommand>(), Ok(ReviewCommand::Play(Some(1), 3.)));
+        assert_eq!("a".parse::<ReviewCommand>(), Ok(ReviewCommand::Approve));
+        assert!("t x".parse::<ReviewCommand>().is_err());
+        assert!("n 1".parse::<ReviewCommand>().is_err());
+
+        assert_eq!(
+            player_args(DEFAULT_PLAYER, Path::new("/media/day 1/ZOOM0001.WAV"), 61.25, 5.),
+            vec!["ffplay", "-nodisp", "-autoexit", "-loglevel", "quiet", "-ss", "61.250", "-t", "5.000", "/media/day 1/ZOOM0001.WAV"],
+        );
+
+        let mut review = Review::new(vec![], &[]);
+        let mut output = vec![];
+        review.run("a\nq\n".as_bytes(), &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "# no groups to review\n");
+        assert!(review.manifest().groups.is_empty());
+    }
+
+    #[test]
+    fn test_review_session() {
+        let dir = std::env::temp_dir().join("smergy-test-review");
+        let _ = std::fs::remove_dir_all(&dir);
+        let video = phone_video(&dir.join("VID_1.mp4"), 60., "10:16:00");
+        let wrong = zoom_audio(&dir.join("ZOOM0001.WAV"), 30., "10:15:40");
+        let late = zoom_audio(&dir.join("ZOOM0002.WAV"), 30., "10:15:50");
+        let mut review = Review::new(vec![(Clip::single(video.clone()), vec![Clip::single(wrong.clone()), Clip::single(late.clone())])], &[]);
+        let mut output = vec![];
+        review.run("t 0\nn 1 0.5\na\n".as_bytes(), &mut output).unwrap();
+        assert!(String::from_utf8(output).unwrap().ends_with("# that was the last group\n"));
+
+        let manifest = review.manifest();
+        assert_eq!(manifest.groups[0].approved, Some(true));
+        let audio: Vec<(&str, Option<f64>)> = manifest.groups[0].audio.iter().map(|a| (a.files[0].path.as_str(), a.offset)).collect();
+        assert_eq!(audio, vec![(late.full_path(), Some(20.5))]);
+
+        let mut overrides = Overrides::default();
+        review.update_overrides(&mut overrides);
+        let video_hash = video.content_hash().unwrap();
+        assert_eq!(overrides.files[&wrong.content_hash().unwrap()].not_in, vec![video_hash.clone()]);
+        assert_eq!(overrides.files[&late.content_hash().unwrap()].offset, Some(RelativeOffset { from: video_hash, seconds: 20.5 }));
+        assert_eq!(overrides.files.len(), 2);
+
+        // the same in the full-screen view: select the second recording and nudge it right, toggle the first, approve
+        let group = (Clip::single(video.clone()), vec![Clip::single(wrong.clone()), Clip::single(late.clone())]);
+        let mut review = Review::new(vec![group], &[]);
+        let screen = review.screen((24, 80), "", false);
+        assert!(screen.starts_with("review: group 1/1, undecided"));
+        assert!(screen.contains(">[x]  0 ZOOM0001.WAV"));
+        assert!(screen.contains("│"));
+        let mut output = vec![];
+        review.run_screen("j\x1b[C\x1b[1;2Ck a".as_bytes(), &mut output, (24, 80)).unwrap();
+        assert!(String::from_utf8(output).unwrap().starts_with("\x1b[H\x1b[2Jreview: group 1/1"));
+        let manifest = review.manifest();
+        assert_eq!(manifest.groups[0].approved, Some(true));
+        let audio: Vec<(&str, Option<f64>)> = manifest.groups[0].audio.iter().map(|a| (a.files[0].path.as_str(), a.offset)).collect();
+        assert_eq!(audio, vec![(late.full_path(), Some(21.1))]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_terminal_keys() {
+        assert_eq!(parse_keys(b"j\x1b[A\x1b[1;2D \x1bq"), vec![
+            Key::Char('j'), Key::Up, Key::ShiftLeft, Key::Char(' '), Key::Escape, Key::Char('q'),
+        ]);
+        assert_eq!(parse_keys("\x1b[Cé".as_bytes()), vec![Key::Right]);
+    }
+
+    #[test]
+    fn test_watch_settling() {
+        use std::time::{Duration, Instant};
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+        let mut settling = Settling::new(Duration::from_secs(5));
+        settling.touched(PathBuf::from("/card/ZOOM0001.WAV"), at(0));
+        settling.touched(PathBuf::from("/card/VID_1.mp4"), at(0));
+        settling.touched(PathBuf::from("/card/ZOOM0001.WAV.part"), at(0));
+
+        let sizes: HashMap<&str, u64> = vec![("/card/ZOOM0001.WAV", 100), ("/card/VID_1.mp4", 10)].into_iter().collect();
+        let size_of = |path: &Path| sizes.get(path.to_str().unwrap()).copied();
+        assert!(settling.settled(at(1), size_of).is_empty());
+        // the video is still being copied
+        let grown: HashMap<&str, u64> = vec![("/card/ZOOM0001.WAV", 100), ("/card/VID_1.mp4", 20)].into_iter().collect();
+        let grown_size_of = |path: &Path| grown.get(path.to_str().unwrap()).copied();
+        assert!(settling.settled(at(4), grown_size_of).is_empty());
+        assert!(settling.settled(at(8), grown_size_of).is_empty());
+        assert_eq!(
+            settling.settled(at(9), grown_size_of),
+            vec![PathBuf::from("/card/VID_1.mp4"), PathBuf::from("/card/ZOOM0001.WAV")],
+        );
+        assert!(settling.settled(at(20), grown_size_of).is_empty());
+
+        let before: HashMap<String, String> = vec![
+            (String::from("2020-01-05/VID_1"), String::from("a")),
+            (String::from("2020-01-06/VID_2"), String::from("b")),
+        ].into_iter().collect();
+        let mut after = before.clone();
+        after.insert(String::from("2020-01-06/VID_2"), String::from("b c"));
+        after.insert(String::from("2020-01-07/VID_3"), String::from("d"));
+        assert_eq!(changed_sessions(&before, &after), vec![String::from("2020-01-06/VID_2"), String::from("2020-01-07/VID_3")]);
+    }
+
+    #[test]
+    fn test_walk_patterns() {
+        assert!(matches("*.reapeaks", Path::new("day1/ZOOM0001.WAV.reapeaks"), false));
+        assert!(matches("proxies/", Path::new("day1/proxies/VID_1_proxy.mov"), false));
+        assert!(!matches("proxies/", Path::new("day1/proxies"), false));
+        assert!(matches("proxies/", Path::new("day1/proxies"), true));
+        assert!(matches("**/DCIM/**", Path::new("card/DCIM/100GOPRO/GH010001.MP4"), false));
+        assert!(matches("day?/*.WAV", Path::new("day1/ZOOM0001.WAV"), false));
+        assert!(!matches("day?/*.WAV", Path::new("day1/sub/ZOOM0001.WAV"), false));
+        assert!(matches("day1/**/*.WAV", Path::new("day1/ZOOM0001.WAV"), false));
+        assert!(!matches("*.WAV", Path::new("day1/ZOOM0001.wav"), false));
+    }
+
+    #[test]
+    fn test_walk_filter() {
+        let dir = std::env::temp_dir().join("smergy-test-walk");
+        let _ = std::fs::remove_dir_all(&dir);
+        for directory in &["day1/sub/deeper", "day1/.Trash-1000", "day1/skipped", "day2"] {
+            std::fs::create_dir_all(dir.join(directory)).unwrap();
+        }
+        for file in &[
+            "day1/VID_1.mp4", "day1/ZOOM0001.WAV", "day1/ZOOM0001.WAV.reapeaks", "day1/.hidden.wav",
+            "day1/sub/ZOOM0002.WAV", "day1/sub/deeper/ZOOM0003.WAV", "day1/.Trash-1000/old.wav",
+            "day1/skipped/VID_2.mp4", "day2/VID_3.mp4",
+        ] {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        std::fs::write(dir.join("day1").join(IGNORE_FILE), "# recorder junk\n*.reapeaks\nskipped/\n").unwrap();
+        let found = |filter: WalkFilter| {
+            let mut found: Vec<String> = filter
+                .entries(&dir)
+                .iter()
+                .map(|e| e.path().strip_prefix(&dir).unwrap().display().to_string())
+                .filter(|p| !p.ends_with(IGNORE_FILE))
+                .collect();
+            found.sort();
+            found
+        };
+        assert_eq!(
+            found(WalkFilter::default()),
+            vec!["day1/VID_1.mp4", "day1/ZOOM0001.WAV", "day1/sub/ZOOM0002.WAV", "day1/sub/deeper/ZOOM0003.WAV", "day2/VID_3.mp4"],
+        );
+        assert_eq!(found(WalkFilter::default().with_max_depth(Some(2))), vec!["day1/VID_1.mp4", "day1/ZOOM0001.WAV", "day2/VID_3.mp4"]);
+        assert_eq!(
+            found(WalkFilter::default().including(&[String::from("*.WAV")]).excluding(&[String::from("deeper/")])),
+            vec!["day1/ZOOM0001.WAV", "day1/sub/ZOOM0002.WAV"],
+        );
+        assert!(found(WalkFilter::default().with_hidden(true)).contains(&String::from("day1/.Trash-1000/old.wav")));
+
+        let filter = WalkFilter::default();
+        assert!(filter.admits(&dir, &dir.join("day2/VID_4.mp4")));
+        assert!(!filter.admits(&dir, &dir.join("day1/skipped/VID_5.mp4")));
+        assert!(!filter.admits(&dir, &dir.join("day1/.Trash-1000/new.wav")));
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("day1"), dir.join("day1/sub/loop")).unwrap();
+            std::os::unix::fs::symlink(dir.join("day2"), dir.join("day1/linked")).unwrap();
+            assert!(!found(WalkFilter::default()).contains(&String::from("day1/linked/VID_3.mp4")));
+            let followed = found(WalkFilter::default().following_links(true));
+            assert!(followed.contains(&String::from("day1/linked/VID_3.mp4")));
+            assert!(!followed.iter().any(|p| p.contains("loop")));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_date_filter() {
+        // a wednesday
+        let now = chrono::NaiveDate::from_ymd(2020, 1, 8).and_hms(18, 30, 0);
+        let day = |d: u32| chrono::NaiveDate::from_ymd(2020, 1, d).and_hms(0, 0, 0);
+        assert_eq!(parse_moment("2d", false, now), Some(day(6) + chrono::Duration::hours(18) + chrono::Duration::minutes(30)));
+        assert_eq!(parse_moment("90m", false, now), Some(day(8) + chrono::Duration::hours(17)));
+        assert_eq!(parse_moment("2020-01-05", false, now), Some(day(5)));
+        assert_eq!(parse_moment("2020-01-05", true, now), Some(day(6)));
+        assert_eq!(parse_moment("2020-01-05 14:30", true, now), Some(day(5) + chrono::Duration::minutes(14 * 60 + 30)));
+        assert_eq!(parse_moment("yesterday", false, now), Some(day(7)));
+        assert_eq!(parse_moment("saturday", false, now), Some(day(4)));
+        assert_eq!(parse_moment("Saturday", true, now), Some(day(5)));
+        assert_eq!(parse_moment("wednesday", false, now), Some(day(1)));
+        assert_eq!(parse_moment("2x", false, now), None);
+
+        let saturday = DateFilter::new(Some(day(4)), Some(day(5)));
+        let at = |d: u32, h: i64| Some(day(d) + chrono::Duration::hours(h));
+        assert!(saturday.keeps(at(4, 14), at(4, 15)));
+        // started the evening before
+        assert!(saturday.keeps(at(3, 23), at(4, 1)));
+        assert!(!saturday.keeps(at(5, 0), at(5, 1)));
+        assert!(!saturday.keeps(at(3, 10), at(3, 11)));
+        assert!(!saturday.keeps(None, None));
+        assert!(DateFilter::default().keeps(None, None));
+        assert!(DateFilter::new(None, Some(day(5))).modified_since().is_none());
+        assert!(saturday.modified_since().unwrap() < DateFilter::new(Some(day(5)), None).modified_since().unwrap());
+    }
+
+    #[test]
+    fn test_project_names() {
+        let name = ProjectName {
+            date: String::from("2020-01-04"),
+            time: String::from("101500"),
+            session: String::from("VID_20200104_101500"),
+            device: String::from("a/b"),
+            first_file: String::from("ZOOM0001"),
+        };
+        assert_eq!(name.render(DEFAULT_NAME), PathBuf::from("VID_20200104_101500"));
+        assert_eq!(name.render("{date}/{time}-{device}-{first_file}"), PathBuf::from("2020-01-04/101500-a_b-ZOOM0001"));
+
+        let dir = std::env::temp_dir().join("smergy-test-project-names");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("2020-01-04")).unwrap();
+        let output = ProjectOutput::new(dir.clone(), "{date}/{session}");
+        let taken = dir.join("2020-01-04/VID_20200104_101500.rpp");
+        assert_eq!(output.path_for(&name, "rpp"), Some(taken.clone()));
+        std::fs::write(&taken, "").unwrap();
+        assert_eq!(output.path_for(&name, "rpp"), Some(dir.join("2020-01-04/VID_20200104_101500-2.rpp")));
+        assert_eq!(output.path_for(&name, "mlt"), Some(dir.join("2020-01-04/VID_20200104_101500.mlt")));
+        assert_eq!(output.clone().with_collision(Collision::Overwrite).path_for(&name, "rpp"), Some(taken.clone()));
+        assert_eq!(output.with_collision(Collision::Skip).path_for(&name, "rpp"), None);
+
+        assert_eq!(keep_backup(&dir.join("2020-01-04/VID_20200104_101500.mlt")).unwrap(), None);
+        std::fs::write(&taken, "edited by hand").unwrap();
+        let backup = dir.join("2020-01-04/VID_20200104_101500.rpp-bak");
+        assert_eq!(keep_backup(&taken).unwrap(), Some(backup.clone()));
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "edited by hand");
+        assert_eq!(backup_path(Path::new("projects/a.b.mlt")), PathBuf::from("projects/a.b.mlt-bak"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_relative_media_paths() {
+        let project = Path::new("/footage/projects/2020-01-04");
+        assert_eq!(relative_path(Path::new("/footage/cards/VID_1.mp4"), project), Some(PathBuf::from("../../cards/VID_1.mp4")));
+        assert_eq!(relative_path(Path::new("/footage/projects/2020-01-04/a.wav"), project), Some(PathBuf::from("a.wav")));
+        assert_eq!(relative_path(Path::new("/media/card/VID_1.mp4"), project), None);
+        assert_eq!(relative_path(Path::new("cards/VID_1.mp4"), project), None);
+
+        assert!(Cli::from_iter_safe(&["smergy", "export", "-d", ".", "--overwrite", "--output-dir", "projects"]).is_ok());
+        assert!(Cli::from_iter_safe(&["smergy", "export", "-d", ".", "--overwrite", "--skip-existing"]).is_err());
+    }
+
+    #[test]
+    fn test_timecode_jumps() {
+        assert_eq!(parse_timecode("10:00:00:12", (25, 1)), Some(36000.48));
+        assert_eq!(parse_timecode("10:00:00;15", (30000, 1001)), Some(36000. + 15. * 1001. / 30000.));
+        assert_eq!(parse_timecode("10:00:00", (25, 1)), None);
+
+        let dir = std::env::temp_dir().join("smergy-test-timecode");
+        let _ = std::fs::remove_dir_all(&dir);
+        let part = |name: &str, seconds: u64| probed(&dir.join(name), 10., &format!(r#"{{"time_reference": "{}"}}"#, seconds * 48000));
+        let clip = Clip::new(vec![part("A001.WAV", 36000), part("A002.WAV", 36010), part("A003.WAV", 36300)]);
+        assert_eq!(clip.parts[0].timecode(), Some(36000.));
+        let jumps = timecode_jumps(&clip);
+        assert_eq!(jumps.len(), 1);
+        assert_eq!(jumps[0].0.filename(), "A003.WAV");
+        assert_eq!(jumps[0].1.position, 0.);
+        assert_eq!(jumps[0].1.label, "timecode jump +280.000s A003.WAV");
+
+        let video = probed(&dir.join("C0001.MP4"), 10., r#"{"timecode": "10:00:00:12"}"#);
+        assert_eq!(video.timecode(), Some(36000.48));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_clips_from_files() {
+        let dir = std::env::temp_dir().join("smergy-test-clips");
+        let _ = std::fs::remove_dir_all(&dir);
+        let files = vec![
+            zoom_audio(&dir.join("ZOOM0001.WAV"), 10., "10:00:10"),
+            // split by the recorder, starts when the first one ends
+            zoom_audio(&dir.join("ZOOM0002.WAV"), 10., "10:00:20"),
+            // starts 2s before the one above ends
+            zoom_audio(&dir.join("ZOOM0003.WAV"), 10., "10:00:28"),
+            // a take started 3s after the one above
+            zoom_audio(&dir.join("ZOOM0004.WAV"), 10., "10:00:41"),
+        ];
+        let clips = Clip::from_files(files);
+        let names: Vec<Vec<String>> = clips.iter().map(|c| c.parts.iter().map(|p| p.filename()).collect()).collect();
+        assert_eq!(names, vec![
+            vec![String::from("ZOOM0001.WAV"), String::from("ZOOM0002.WAV")],
+            vec![String::from("ZOOM0003.WAV")],
+            vec![String::from("ZOOM0004.WAV")],
+        ]);
+
+        // back to back takes of a recorder writing a file per track
+        let files = vec![
+            zoom_audio(&dir.join("h6/ZOOM0001_Tr1.WAV"), 10., "11:00:10"),
+            zoom_audio(&dir.join("h6/ZOOM0001_Tr2.WAV"), 10., "11:00:10"),
+            zoom_audio(&dir.join("h6/ZOOM0002_Tr1.WAV"), 10., "11:00:20"),
+            zoom_audio(&dir.join("h6/ZOOM0002_Tr2.WAV"), 10., "11:00:20"),
+        ];
+        let clips = Clip::from_files(files);
+        let mut names: Vec<Vec<String>> = clips.iter().map(|c| c.parts.iter().map(|p| p.filename()).collect()).collect();
+        names.sort();
+        assert_eq!(names, vec![
+            vec![String::from("ZOOM0001_Tr1.WAV"), String::from("ZOOM0002_Tr1.WAV")],
+            vec![String::from("ZOOM0001_Tr2.WAV"), String::from("ZOOM0002_Tr2.WAV")],
+        ]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_proxy_directory_skipped() {
+        let dir = std::env::temp_dir().join("smergy-test-proxy-directory");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("proxies")).unwrap();
+        std::fs::write(dir.join("VID_1.mp4"), "video").unwrap();
+        std::fs::write(dir.join("proxies/VID_1_proxy.partial.mp4"), "interrupted").unwrap();
+        mark_proxy_directory(&dir.join("proxies")).unwrap();
+        let walked: Vec<PathBuf> = WalkFilter::default().entries(&dir).iter().map(|e| e.path().to_path_buf()).collect();
+        assert_eq!(walked, vec![dir.join("VID_1.mp4")]);
+        // an index already there is left alone
+        remember_original(&dir.join("proxies/VID_1_proxy.mp4"), &dir.join("VID_1.mp4")).unwrap();
+        mark_proxy_directory(&dir.join("proxies")).unwrap();
+        assert_eq!(original_of("proxies/VID_1_proxy.mp4", &dir), Some(dir.join("VID_1.mp4").display().to_string()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_deduplicate_overlapping_directories() {
+        let dir = std::env::temp_dir().join("smergy-test-dedup-overlap");
+        let _ = std::fs::remove_dir_all(&dir);
+        let video = phone_video(&dir.join("day1/VID_1.mp4"), 10., "10:00:10");
+        std::fs::create_dir_all(dir.join("backup")).unwrap();
+        std::fs::copy(dir.join("day1/VID_1.mp4"), dir.join("backup/VID_1.mp4")).unwrap();
+        let backup = phone_video(&dir.join("backup/VID_1.mp4"), 10., "10:00:10");
+        // found walking both the footage directory and day1 inside it
+        let again = phone_video(&dir.join("day1/../day1/VID_1.mp4"), 10., "10:00:10");
+        let files = deduplicate(vec![video, backup, again]);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].duplicates(), &[dir.join("backup/VID_1.mp4")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rescanned_after_deleting() {
+        let dir = std::env::temp_dir().join("smergy-test-rescanned");
+        let _ = std::fs::remove_dir_all(&dir);
+        let (card, backup) = (dir.join("card/VID_1.mp4"), dir.join("backup/VID_1.mp4"));
+        let probe = |path: &Path| match path.exists() {
+            true => vec![phone_video(path, 10., "10:00:10")],
+            false => vec![],
+        };
+        let scan = || {
+            phone_video(&card, 10., "10:00:10");
+            std::fs::create_dir_all(dir.join("backup")).unwrap();
+            std::fs::copy(&card, &backup).unwrap();
+            deduplicate(probe(&card).into_iter().chain(probe(&backup)).collect())
+        };
+
+        // the copy in use is deleted, the backup takes over
+        let files = scan();
+        std::fs::remove_file(&card).unwrap();
+        let files = rescanned(files, &[card.as_path()], probe);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].entry.path(), backup);
+        assert!(files[0].duplicates().is_empty());
+
+        // the backup is deleted, it's no longer listed
+        let files = scan();
+        std::fs::remove_file(&backup).unwrap();
+        let files = rescanned(files, &[backup.as_path()], probe);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].entry.path(), card);
+        assert!(files[0].duplicates().is_empty());
+
+        // a copy turning up is added to the file it copies, another recording is added on its own
+        let copy = dir.join("backup/VID_1 copy.mp4");
+        std::fs::copy(&card, &copy).unwrap();
+        let other = dir.join("card/VID_2.mp4");
+        phone_video(&other, 20., "10:00:40");
+        let files = rescanned(files, &[copy.as_path(), other.as_path()], probe);
+        assert_eq!(files.iter().map(|f| f.entry.path()).collect::<Vec<_>>(), vec![card.as_path(), other.as_path()]);
+        assert_eq!(files[0].duplicates(), &[copy]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mlt_drift() {
+        let dir = std::env::temp_dir().join("smergy-test-mlt-drift");
+        let _ = std::fs::remove_dir_all(&dir);
+        let video = Clip::single(phone_video(&dir.join("VID_1.mp4"), 60., "10:16:00"));
+        // 1000s played 100ppm fast take 999.9s, 24997.5 frames
+        let audio = Clip::single(zoom_audio(&dir.join("ZOOM0001.WAV"), 1000., "10:30:00")).with_drift(100.);
+        let project = Mlt::from_group(&video, &[audio], ChannelSplit::None).unwrap().project_file();
+        assert!(project.contains("<property name=\"mlt_service\">timewarp</property>"));
+        assert!(project.contains(&format!("<property name=\"resource\">1.0001:{}</property>", dir.join("ZOOM0001.WAV").display())));
+        assert!(project.contains("<property name=\"length\">24998</property>\n    <property name=\"resource\">1.0001:"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_absolute_media_paths() {
+        let dir = std::env::temp_dir().join("smergy-test-absolute-paths");
+        let _ = std::fs::remove_dir_all(&dir);
+        // scanned by a path relative to the current directory, --absolute-paths writes where it really is
+        let up: PathBuf = std::env::current_dir().unwrap().components().skip(1).map(|_| "..").collect();
+        let scanned = up.join(dir.strip_prefix("/").unwrap());
+        let video = Clip::single(phone_video(&scanned.join("VID_1.mp4"), 60., "10:16:00"));
+        let audios = [Clip::single(zoom_audio(&scanned.join("ZOOM0001.WAV"), 30., "10:15:50"))];
+        let absolute = |name: &str| format!("{}", dir.join(name).display());
+
+        let project = Reaper::from_group(&video, &audios, &[], ChannelSplit::None).with_absolute_paths().project_file();
+        assert!(project.contains(&format!("FILE \"{}\"", absolute("VID_1.mp4"))));
+        assert!(project.contains(&format!("FILE \"{}\"", absolute("ZOOM0001.WAV"))));
+        let project = Mlt::from_group(&video, &audios, ChannelSplit::None).unwrap().with_absolute_paths().project_file();
+        assert!(project.contains(&format!("<property name=\"resource\">{}</property>", absolute("VID_1.mp4"))));
+        assert!(!project.contains(".."));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(Cli::from_iter_safe(&["smergy", "export", "-d", ".", "--absolute-paths"]).is_ok());
+    }
+
+    #[test]
+    fn test_channel_split() {
+        assert_eq!(ChannelSplit::Mono.mappings(2), vec![ChannelMapping::All]);
+        assert_eq!(ChannelSplit::None.mappings(6), vec![ChannelMapping::All]);
+        assert_eq!(
+            ChannelSplit::Mono.mappings(3),
+            vec![ChannelMapping::Mono(1), ChannelMapping::Mono(2), ChannelMapping::Mono(3)],
+        );
+        assert_eq!(
+            ChannelSplit::Pairs.mappings(5),
+            vec![ChannelMapping::Pair(1), ChannelMapping::Pair(3), ChannelMapping::Mono(5)],
+        );
+        let mut names = HashMap::new();
+        names.insert(3, String::from("Boom"));
+        assert_eq!(ChannelMapping::Mono(3).label(&names), Some(String::from("Boom")));
+        assert_eq!(ChannelMapping::Mono(4).label(&names), Some(String::from("ch4")));
+        assert_eq!(ChannelMapping::Pair(3).label(&names), Some(String::from("Boom + ch4")));
+        assert_eq!(ChannelMapping::Pair(1).label(&names), Some(String::from("ch1-2")));
+        assert_eq!(ChannelMapping::All.label(&names), None);
+    }
+    #[test]
+    fn test_ixml_track_names() {
+        let xml = "<BWFXML><TRACK_LIST><TRACK_COUNT>2</TRACK_COUNT>\
+            <TRACK><CHANNEL_INDEX>1</CHANNEL_INDEX><INTERLEAVE_INDEX>1</INTERLEAVE_INDEX><NAME>Boom</NAME></TRACK>\
+            <TRACK><CHANNEL_INDEX>2</CHANNEL_INDEX><NAME></NAME></TRACK>\
+            <TRACK><CHANNEL_INDEX>3</CHANNEL_INDEX><NAME>Lav Anna</NAME></TRACK></TRACK_LIST></BWFXML>";
+        let dir = std::env::temp_dir().join("smergy-test-ixml");
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut wav: Vec<u8> = b"RIFF\0\0\0\0WAVE".to_vec();
+        wav.extend(b"data");
+        wav.extend(&3u32.to_le_bytes());
+        wav.extend(&[0, 0, 0, 0]); // odd sized chunk plus its padding byte
+        wav.extend(b"iXML");
+        wav.extend(&(xml.len() as u32).to_le_bytes());
+        wav.extend(xml.as_bytes());
+        std::fs::write(dir.join("poly.wav"), wav).unwrap();
+        let names = track_names(&ixml_chunk(dir.join("poly.wav")).unwrap());
+        assert_eq!(names.len(), 2);
+        assert_eq!(names.get(&1), Some(&String::from("Boom")));
+        assert_eq!(names.get(&3), Some(&String::from("Lav Anna")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn test_channel_mapping_exports() {
+        let track = |mapping| ReaperTrack::empty(String::from("A"), String::from("A"))
+            .with_item(ReaperItem::new(
+                String::from("88564052-9129-3D60-867F-7840E1CD613E"),
+                String::from("007E36C0-EC22-CB19-1344-D87A1BAB7798"),
+                String::from("ZOOM0001.WAV"),
+                String::from("/media/ZOOM0001.WAV"),
+                String::from("10"),
+                MediaType::Audio,
+            ).with_channel_mapping(mapping))
+            .as_node();
+        let chanmode = |mapping| track(mapping).find("ITEM").unwrap().find("CHANMODE").unwrap().to_string();
+        assert_eq!(chanmode(ChannelMapping::All), "CHANMODE 0\n");
+        assert_eq!(chanmode(ChannelMapping::Mono(1)), "CHANMODE 3\n");
+        assert_eq!(chanmode(ChannelMapping::Pair(3)), "CHANMODE 69\n");
+
+        let dir = std::env::temp_dir().join("smergy-test-channel-mapping");
+        let _ = std::fs::remove_dir_all(&dir);
+        let wav = zoom_audio(&dir.join("ZOOM0001.WAV"), 10., "10:15:10");
+        let nchan = |mapping| ReaperTrack::for_channels(&wav, "session", mapping).as_node().find("NCHAN").unwrap().to_string();
+        assert_eq!(nchan(ChannelMapping::Mono(2)), "NCHAN 2\n");
+        assert_eq!(nchan(ChannelMapping::Pair(1)), "NCHAN 2\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let project = Mlt::new(
+            MltProfile::new(1920, 1080, 25, 1),
+            vec![
+                MltClip::new(String::from("VID_1.mp4"), String::from("/media/VID_1.mp4"), 0., 1., MediaType::Video),
+                MltClip::new(String::from("ZOOM0001.WAV ch3"), String::from("/media/ZOOM0001.WAV"), 0., 1., MediaType::Audio)
+                    .with_channel_mapping(ChannelMapping::Mono(3)),
+            ],
+        ).project_file();
+        assert!(project.contains(r#"
+    <entry producer="producer1" in="0" out="24">
+      <filter mlt_service="channelcopy">
+        <property name="from">2</property>
+        <property name="to">0</property>
+      </filter>
+      <filter mlt_service="channelcopy">
+        <property name="from">2</property>
+        <property name="to">1</property>
+      </filter>
+    </entry>"#));
+    }
+
+    const REAPER_EXAMPLE: &str = r#"<REAPER_PROJECT 0.1 "5.983/linux64" 1578133917
+  RIPPLE 0
+  GROUPOVERRIDE 0 0 0
+  AUTOXFADE 1
+  ENVATTACH 1
+  POOLEDENVATTACH 0
+  MIXERUIFLAGS 11 48
+  PEAKGAIN 1
+  FEEDBACK 0
+  PANLAW 1
+  PROJOFFS 0 0 0
+  MAXPROJLEN 0 600
+  GRID 3199 8 1 8 1 0 0 0
+  TIMEMODE 1 5 -1 30 0 0 -1
+  VIDEO_CONFIG 0 0 256
+  PANMODE 3
+  CURSOR 56
+  ZOOM 1.1747815778626 0 0
+  VZOOMEX 6
+  USE_REC_CFG 0
+  RECMODE 1
+  SMPTESYNC 0 30 100 40 1000 300 0 0 1 0 0
+  LOOP 0
+  LOOPGRAN 0 4
+  RECORD_PATH "" ""
+  <RECORD_CFG
+  >
+  <APPLYFX_CFG
+  >
+  RENDER_FILE ""
+  RENDER_PATTERN ""
+  RENDER_FMT 0 2 0
+  RENDER_1X 0
+  RENDER_RANGE 1 0 0 18 1000
+  RENDER_RESAMPLE 3 0 1
+  RENDER_ADDTOPROJ 0
+  RENDER_STEMS 0
+  RENDER_DITHER 0
+  TIMELOCKMODE 1
+  TEMPOENVLOCKMODE 1
+  ITEMMIX 0
+  DEFPITCHMODE 589824 0
+  TAKELANE 1
+  SAMPLERATE 44100 0 0
+  <RENDER_CFG
+  >
+  LOCK 1
+  <METRONOME 6 2
+    VOL 0.25 0.125
+    FREQ 800 1600 1
+    BEATLEN 4
+    SAMPLES "" ""
+    PATTERN 2863311530 2863311529
+  >
+  GLOBAL_AUTO -1
+  TEMPO 120 4 4
+  PLAYRATE 1 0 0.25 4
+  SELECTION 0 0
+  SELECTION2 0 0
+  MASTERAUTOMODE 0
+  MASTERTRACKHEIGHT 0 0
+  MASTERPEAKCOL 16576
+  MASTERMUTESOLO 0
+  MASTERTRACKVIEW 0 0.6667 0.5 0.5 0 0 0
+  MASTERHWOUT 0 0 1 0 0 0 0 -1
+  MASTER_NCH 2 2
+  MASTER_VOLUME 1 0 -1 -1 1
+  MASTER_FX 1
+  MASTER_SEL 0
+  <MASTERPLAYSPEEDENV
+    ACT 0 -1
+    VIS 0 1 1
+    LANEHEIGHT 0 0
+    ARM 0
+    DEFSHAPE 0 -1 -1
+  >
+  <TEMPOENVEX
+    ACT 0 -1
+    VIS 1 0 1
+    LANEHEIGHT 0 0
+    ARM 0
+    DEFSHAPE 1 -1 -1
+  >
+  <PROJBAY
+  >
+  <TRACK {45571899-9E84-5151-B211-B16E459C356D}
+    NAME "BE FOUND SOON_m1.1"
+    PEAKCOL 16576
+    BEAT -1
+    AUTOMODE 0
+    VOLPAN 1 0 -1 -1 1
+    MUTESOLO 0 0 0
+    IPHASE 0
+    ISBUS 0 0
+    BUSCOMP 0 0
+    SHOWINMIX 1 0.6667 0.5 1 0.5 0 0 0
+    FREEMODE 0
+    SEL 0
+    REC 0 0 0 0 0 0 0
+    VU 2
+    TRACKHEIGHT 0 0 0
+    INQ 0 0 0 0.5 100 0 0 100
+    NCHAN 2
+    FX 1
+    TRACKID {45571899-9E84-5151-B211-B16E459C356D}
+    PERF 0
+    MIDIOUT -1
+    MAINSEND 1 0
+    <ITEM
+      POSITION 0
+      SNAPOFFS 0
+      LENGTH 206.69117913832199
+      LOOP 1
+      ALLTAKES 0
+      FADEIN 1 0.01 0 1 0 0
+      FADEOUT 1 0.01 0 1 0 0
+      MUTE 0
+      SEL 0
+      IGUID {88564052-9129-3D60-867F-7840E1CD613E}
+      IID 1
+      NAME "BE FOUND SOON_m1.1.wav"
+      VOLPAN 1 0 1 -1
+      SOFFS 0
+      PLAYRATE 1 1 0 -1 0 0.0025
+      CHANMODE 0
+      GUID {007E36C0-EC22-CB19-1344-D87A1BAB7798}
+      <SOURCE WAVE
+        FILE "/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"
+      >
+    >
+  >
+>
+"#;
+
+    const REAPER_TRACK_EXAMPLE: &str = r#"<TRACK {45571899-9E84-5151-B211-B16E459C356D}
+  NAME "BE FOUND SOON_m1.1"
+  PEAKCOL 16576
+  BEAT -1
+  AUTOMODE 0
+  VOLPAN 1 0 -1 -1 1
+  MUTESOLO 0 0 0
+  IPHASE 0
+  ISBUS 0 0
+  BUSCOMP 0 0
+  SHOWINMIX 1 0.6667 0.5 1 0.5 0 0 0
+  FREEMODE 0
+  SEL 0
+  REC 0 0 0 0 0 0 0
+  VU 2
+  TRACKHEIGHT 0 0 0
+  INQ 0 0 0 0.5 100 0 0 100
+  NCHAN 2
+  FX 1
+  TRACKID {45571899-9E84-5151-B211-B16E459C356D}
+  PERF 0
+  MIDIOUT -1
+  MAINSEND 1 0
+  <ITEM
+    POSITION 0
+    SNAPOFFS 0
+    LENGTH 206.69117913832199
+    LOOP 1
+    ALLTAKES 0
+    FADEIN 1 0.01 0 1 0 0
+    FADEOUT 1 0.01 0 1 0 0
+    MUTE 0
+    SEL 0
+    IGUID {88564052-9129-3D60-867F-7840E1CD613E}
+    IID 1
+    NAME "BE FOUND SOON_m1.1.wav"
+    VOLPAN 1 0 1 -1
+    SOFFS 0
+    PLAYRATE 1 1 0 -1 0 0.0025
+    CHANMODE 0
+    GUID {007E36C0-EC22-CB19-1344-D87A1BAB7798}
+    <SOURCE WAVE
+      FILE "/home/niedzwiedz/Downloads/BE FOUND SOON_m1.1.wav"
+    >
+  >
+>
+"#;
 }
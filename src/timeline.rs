@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{NaiveDateTime, Duration};
+
+use crate::clip::Clip;
+use crate::dates::{parse_from, parse_to};
+use crate::media_file::MediaType;
+use crate::select::VideoAudioGroup;
+
+const GROUPED: char = '█';
+const UNMATCHED: char = '░';
+/// two files of one device at the same time, a sign of a wrong clock
+const CLASH: char = '▓';
+const EMPTY: char = '·';
+/// video and audio recording at the same time
+const SYNCED: char = '=';
+/// only video or only audio recording
+const ALONE: char = '!';
+
+/// a recording drawn on the timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bar {
+    pub row: String,
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub video: bool,
+    /// whether the recording ended up in a group
+    pub grouped: bool,
+}
+
+/// recordings as bars on a shared time axis, a row per device
+pub struct Timeline {
+    bars: Vec<Bar>,
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    width: usize,
+}
+
+impl Timeline {
+    /// spans all of `bars`, none when there's nothing to draw
+    pub fn new(bars: Vec<Bar>) -> Option<Self> {
+        let from = bars.iter().map(|b| b.start).min()?;
+        let to = bars.iter().map(|b| b.end).max()?;
+        Some(Self { bars, from, to, width: 80 })
+    }
+
+    /// every clip with a known start and end, marked by whether it's in one of `groups`
+    pub fn of(clips: &[Clip], groups: &[VideoAudioGroup]) -> Option<Self> {
+        let grouped: Vec<&str> = groups
+            .iter()
+            .flat_map(|(video, audios)| std::iter::once(video).chain(audios.iter()))
+            .map(|c| c.first().full_path())
+            .collect();
+        let bars = clips
+            .iter()
+            .filter_map(|clip| Some(Bar {
+                row: String::from(clip.device().unwrap_or("unknown")),
+                start: clip.start()?,
+                end: clip.end()?,
+                video: matches!(clip.media_type(), Some(MediaType::Video)),
+                grouped: grouped.contains(&clip.first().full_path()),
+            }))
+            .collect();
+        Self::new(bars)
+    }
+
+    /// shows only `range`
+    pub fn zoomed(mut self, range: &DateRange) -> Self {
+        self.from = range.from;
+        self.to = range.to;
+        self
+    }
+
+    /// shows the video of a session with a little of what happened around it
+    pub fn around(self, video: &Clip) -> Self {
+        match (video.start(), video.end()) {
+            (Some(start), Some(end)) => {
+                let margin = (end - start) / 10;
+                self.zoomed(&DateRange { from: start - margin, to: end + margin })
+            },
+            _ => self,
+        }
+    }
+
+    /// columns the time axis is drawn over
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width.max(10);
+        self
+    }
+
+    /// start and end of every column
+    fn columns(&self) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+        let span = (self.to - self.from).num_milliseconds().max(1);
+        let at = |column: usize| self.from + Duration::milliseconds(span * column as i64 / self.width as i64);
+        (0..self.width).map(|c| (at(c), at(c + 1))).collect()
+    }
+
+    fn covering<'a>(bars: impl Iterator<Item = &'a Bar>, column: (NaiveDateTime, NaiveDateTime)) -> Vec<&'a Bar> {
+        bars.filter(|b| b.start < column.1 && b.end > column.0).collect()
+    }
+
+    /// rows with videos first, then the recorders, by name
+    fn rows(&self) -> Vec<(&str, Vec<&Bar>)> {
+        let mut rows: BTreeMap<(bool, &str), Vec<&Bar>> = BTreeMap::new();
+        for bar in &self.bars {
+            rows.entry((!bar.video, &bar.row)).or_default().push(bar);
+        }
+        let mut merged: Vec<(&str, Vec<&Bar>)> = vec![];
+        for ((_, row), bars) in rows {
+            match merged.iter_mut().find(|(name, _)| *name == row) {
+                Some((_, existing)) => existing.extend(bars),
+                None => merged.push((row, bars)),
+            }
+        }
+        merged
+    }
+
+    pub fn render(&self) -> String {
+        let columns = self.columns();
+        let rows = self.rows();
+        let label_width = rows.iter().map(|(name, _)| name.chars().count()).chain(Some(4)).max().unwrap_or(4);
+        let time = |t: NaiveDateTime| t.format("%Y-%m-%d %H:%M:%S").to_string();
+        let (from, to) = (time(self.from), time(self.to));
+        let mut lines = vec![format!(
+            "{:label$}  {}{}{}",
+            "",
+            from,
+            " ".repeat(self.width.saturating_sub(from.len() + to.len()).max(1)),
+            to,
+            label = label_width,
+        )];
+        for (name, bars) in &rows {
+            let cells: String = columns
+                .iter()
+                .map(|column| {
+                    let covering = Self::covering(bars.iter().copied(), *column);
+                    match covering.as_slice() {
+                        [] => EMPTY,
+                        [bar] if bar.grouped => GROUPED,
+                        [_] => UNMATCHED,
+                        _ => CLASH,
+                    }
+                })
+                .collect();
+            lines.push(format!("{:>label$}  {}", name, cells, label = label_width));
+        }
+        let sync: String = columns
+            .iter()
+            .map(|column| {
+                let covering = Self::covering(self.bars.iter(), *column);
+                let video = covering.iter().any(|b| b.video);
+                let audio = covering.iter().any(|b| !b.video);
+                match (video, audio) {
+                    (true, true) => SYNCED,
+                    (false, false) => ' ',
+                    _ => ALONE,
+                }
+            })
+            .collect();
+        lines.push(format!("{:>label$}  {}", "sync", sync, label = label_width));
+        lines.push(format!(
+            "\n{} grouped  {} unmatched  {} overlapping files of one device  {} video and audio  {} video or audio alone",
+            GROUPED, UNMATCHED, CLASH, SYNCED, ALONE,
+        ));
+        lines.join("\n")
+    }
+}
+
+/// a span of time, written as `FROM..TO` with each end a moment `--from` and `--to` take, eg. `2020-01-05 14:00..2020-01-05 16:30`.
+/// a date alone means the whole day
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateRange {
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+}
+
+impl FromStr for DateRange {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (from, to) = match s.find("..") {
+            Some(index) => (&s[..index], &s[index + 2..]),
+            None => (s, s),
+        };
+        let (from, to) = (parse_from(from)?, parse_to(to)?);
+        match from < to {
+            true => Ok(Self { from, to }),
+            false => Err(format!("{} doesn't end after it starts", s)),
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use walkdir::{DirEntry, WalkDir};
+
+use crate::proxy::PROXY_INDEX;
+
+/// patterns in this file leave out matching files and directories of the directory it's in, and below
+pub const IGNORE_FILE: &str = ".smergyignore";
+
+/// which files a directory walk looks at.
+/// patterns are globs (`*`, `**` and `?`) relative to the directory walked. patterns without a `/` match a name at any depth,
+/// a trailing `/` matches directories only
+#[derive(Debug, Clone, Default)]
+pub struct WalkFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    max_depth: Option<usize>,
+    follow_links: bool,
+    hidden: bool,
+    modified_since: Option<SystemTime>,
+}
+
+impl WalkFilter {
+    /// only files matching one of `patterns`, all of them when there are none
+    pub fn including(mut self, patterns: &[String]) -> Self {
+        self.include = patterns.to_vec();
+        self
+    }
+
+    pub fn excluding(mut self, patterns: &[String]) -> Self {
+        self.exclude = patterns.to_vec();
+        self
+    }
+
+    /// how many directories deep to look, files right in the walked directory are at depth 1
+    pub fn with_max_depth(mut self, max_depth: Option<usize>) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// follows symbolic links, links back to a directory being walked are skipped
+    pub fn following_links(mut self, follow_links: bool) -> Self {
+        self.follow_links = follow_links;
+        self
+    }
+
+    /// looks at files and directories whose names start with a dot too
+    pub fn with_hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// skips files last modified before `time`, without probing them
+    pub fn modified_since(mut self, time: Option<SystemTime>) -> Self {
+        self.modified_since = time;
+        self
+    }
+
+    /// files under `root` the filter lets through
+    pub fn entries(&self, root: &Path) -> Vec<DirEntry> {
+        let mut ignores = Ignores::default();
+        let mut walk = WalkDir::new(root).follow_links(self.follow_links);
+        if let Some(max_depth) = self.max_depth {
+            walk = walk.max_depth(max_depth);
+        }
+        walk.into_iter()
+            .filter_entry(|e| self.allows(root, e.path(), e.file_type().is_dir(), &mut ignores))
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(error) => {
+                    if let (Some(ancestor), Some(path)) = (error.loop_ancestor(), error.path()) {
+                        eprintln!("# skipping {}, it links back to {}", path.display(), ancestor.display());
+                    }
+                    None
+                },
+            })
+            .filter(|e| e.file_type().is_file())
+            .filter(|e| match (self.modified_since, e.metadata().ok().and_then(|m| m.modified().ok())) {
+                (Some(since), Some(modified)) => modified >= since,
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// whether a file found below `root` by other means, eg. watching, would have been walked
+    pub fn admits(&self, root: &Path, path: &Path) -> bool {
+        let depth = path.strip_prefix(root).map(|p| p.components().count()).unwrap_or(usize::MAX);
+        let mut ignores = Ignores::default();
+        self.max_depth.is_none_or(|max| depth <= max)
+            && path.ancestors().take_while(|a| a.starts_with(root)).all(|a| self.allows(root, a, a != path, &mut ignores))
+    }
+
+    fn allows(&self, root: &Path, path: &Path, is_dir: bool, ignores: &mut Ignores) -> bool {
+        let relative = match path.strip_prefix(root) {
+            Ok(relative) if relative.as_os_str().is_empty() => return true,
+            Ok(relative) => relative,
+            Err(_) => return true,
+        };
+        if !self.hidden && names(relative).iter().any(|n| n.starts_with('.')) {
+            return false
+        }
+        if is_dir && path.join(PROXY_INDEX).exists() {
+            return false
+        }
+        if self.exclude.iter().any(|p| matches(p, relative, is_dir)) {
+            return false
+        }
+        let ignored = path
+            .ancestors()
+            .skip(1)
+            .take_while(|a| a.starts_with(root))
+            .any(|directory| {
+                let relative = path.strip_prefix(directory).unwrap_or(path);
+                ignores.of(directory).iter().any(|p| matches(p, relative, is_dir))
+            });
+        if ignored {
+            return false
+        }
+        is_dir || self.include.is_empty() || self.include.iter().any(|p| matches(p, relative, is_dir))
+    }
+}
+
+/// patterns of the ignore files read so far, by directory
+#[derive(Default)]
+struct Ignores {
+    patterns: HashMap<PathBuf, Vec<String>>,
+}
+
+impl Ignores {
+    fn of(&mut self, directory: &Path) -> &[String] {
+        self.patterns.entry(directory.to_path_buf()).or_insert_with(|| {
+            fs::read_to_string(directory.join(IGNORE_FILE))
+                .map(|ignore| {
+                    ignore
+                        .lines()
+                        .map(|l| l.trim())
+                        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        })
+    }
+}
+
+fn names(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// whether `pattern` matches `relative` (a directory when `is_dir`) or a directory it's in
+pub fn matches(pattern: &str, relative: &Path, is_dir: bool) -> bool {
+    let directories_only = pattern.ends_with('/');
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    let names = names(relative);
+    // the path itself, then the directories it's in
+    let candidates = (1..=names.len()).rev().filter(|end| is_dir || !directories_only || *end < names.len());
+    let pattern: Vec<char> = pattern.chars().collect();
+    for end in candidates {
+        let text: Vec<char> = match pattern.contains(&'/') {
+            true => names[..end].join("/").chars().collect(),
+            false => names[end - 1].chars().collect(),
+        };
+        if glob(&pattern, &text) {
+            return true
+        }
+    }
+    false
+}
+
+/// `*` and `?` stay within a directory, `**` spans any number of them
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            (0..=text.len()).filter(|i| *i == 0 || text[i - 1] == '/').any(|i| glob(rest, &text[i..]))
+        },
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        ['*', rest @ ..] => {
+            (0..=text.len()).take_while(|i| *i == 0 || text[i - 1] != '/').any(|i| glob(rest, &text[i..]))
+        },
+        ['?', rest @ ..] => !text.is_empty() && text[0] != '/' && glob(rest, &text[1..]),
+        [c, rest @ ..] => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
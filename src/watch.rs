@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::ingest::Session;
+use crate::media_file::supported_extensions;
+use crate::select::VideoAudioGroup;
+
+/// files that changed, waiting to stop growing before they're probed
+pub struct Settling {
+    /// size at the last check and when it last changed, by path
+    pending: HashMap<PathBuf, (Option<u64>, Instant)>,
+    settle: Duration,
+}
+
+impl Settling {
+    pub fn new(settle: Duration) -> Self {
+        Self { pending: HashMap::new(), settle }
+    }
+
+    /// `path` was written to, created or removed at `now`
+    pub fn touched(&mut self, path: PathBuf, now: Instant) {
+        if is_media(&path) {
+            self.pending.insert(path, (None, now));
+        }
+    }
+
+    /// all the files changed, once none of them has grown (or been touched) for the settle time.
+    /// nothing while any file is still being written, so a whole card is handled at once
+    pub fn settled(&mut self, now: Instant, size_of: impl Fn(&Path) -> Option<u64>) -> Vec<PathBuf> {
+        for (path, (size, changed)) in self.pending.iter_mut() {
+            let current = size_of(path);
+            if current != *size {
+                *size = current;
+                *changed = now;
+            }
+        }
+        if self.pending.is_empty() || self.pending.values().any(|(_, changed)| now.duration_since(*changed) < self.settle) {
+            return vec![]
+        }
+        let mut settled: Vec<PathBuf> = self.pending.drain().map(|(path, _)| path).collect();
+        settled.sort();
+        settled
+    }
+}
+
+fn is_media(path: &Path) -> bool {
+    let extension = match path.extension().and_then(|e| e.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => return false,
+    };
+    supported_extensions().any(|e| **e == extension)
+}
+
+/// what's in each session, to tell which of them changed
+pub fn fingerprints(groups: &[VideoAudioGroup]) -> HashMap<String, String> {
+    groups
+        .iter()
+        .map(|(video, audios)| {
+            let session = Session::of(video);
+            let contents: Vec<String> = std::iter::once(video)
+                .chain(audios.iter())
+                .map(|clip| {
+                    let files: Vec<&str> = clip.parts.iter().map(|p| p.full_path()).collect();
+                    format!("{} {:?}", files.join("+"), clip.start())
+                })
+                .collect();
+            (format!("{}/{}", session.date, session.name), contents.join("\n"))
+        })
+        .collect()
+}
+
+/// sessions that are new or differ between `before` and `after`, by name
+pub fn changed_sessions(before: &HashMap<String, String>, after: &HashMap<String, String>) -> Vec<String> {
+    let mut changed: Vec<String> = after
+        .iter()
+        .filter(|(session, contents)| before.get(*session) != Some(contents))
+        .map(|(session, _)| session.clone())
+        .collect();
+    changed.sort();
+    changed
+}
+
+/// calls `handle` with the media files changed under `directories` whenever they settle. runs until the watcher stops.
+/// inotify (or what the platform has) is used unless `poll` is set or it can't be, then the directories are polled
+pub fn watch_directories<T: AsRef<Path>>(
+    directories: &[T],
+    poll: bool,
+    settle: Duration,
+    mut handle: impl FnMut(&[PathBuf]),
+) -> notify::Result<()> {
+    let (sender, receiver) = channel::<notify::Result<Event>>();
+    let native = match poll {
+        true => None,
+        false => RecommendedWatcher::new(sender.clone(), Config::default())
+            .and_then(|mut watcher| {
+                for directory in directories {
+                    watcher.watch(directory.as_ref(), RecursiveMode::Recursive)?;
+                }
+                Ok(watcher)
+            })
+            .map_err(|e| println!("# can't watch for changes ({}), polling instead", e))
+            .ok(),
+    };
+    let mut _watcher: Box<dyn Watcher> = match native {
+        Some(watcher) => Box::new(watcher),
+        None => {
+            let mut watcher = PollWatcher::new(sender, Config::default().with_poll_interval(settle))?;
+            for directory in directories {
+                watcher.watch(directory.as_ref(), RecursiveMode::Recursive)?;
+            }
+            Box::new(watcher)
+        },
+    };
+
+    let mut settling = Settling::new(settle);
+    let tick = (settle / 4).max(Duration::from_millis(100));
+    loop {
+        match receiver.recv_timeout(tick) {
+            Ok(Ok(event)) => {
+                for path in event.paths {
+                    settling.touched(path, Instant::now());
+                }
+            },
+            Ok(Err(error)) => println!("# watching: {}", error),
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+        let settled = settling.settled(Instant::now(), |path| fs::metadata(path).ok().map(|m| m.len()));
+        if !settled.is_empty() {
+            handle(&settled);
+        }
+    }
+}